@@ -0,0 +1,21 @@
+use bevy::prelude::*;
+use bevy_tiled::TiledMapCenter;
+
+fn main() {
+    App::build()
+        .add_default_plugins()
+        .add_plugin(bevy_tiled::TiledMapPlugin::default())
+        .add_startup_system(setup.system())
+        .run();
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn(bevy_tiled::TiledMapComponents {
+            map_asset: asset_server.load("assets/tiny-map.tmx").unwrap(),
+            center: TiledMapCenter(true),
+            origin: Transform::from_scale(4.0),
+            ..Default::default()
+        })
+        .spawn(Camera2dComponents::default());
+}
@@ -4,7 +4,7 @@ use bevy_tiled::TiledMapCenter;
 fn main() {
     App::build()
         .add_default_plugins()
-        .add_plugin(bevy_tiled::TiledMapPlugin)
+        .add_plugin(bevy_tiled::TiledMapPlugin::default())
         .add_startup_system(setup.system())
         .add_system(camera_movement.system())
         .run();
@@ -4,13 +4,16 @@ use bevy_tiled::TiledMapCenter;
 fn main() {
     App::build()
         .add_default_plugins()
-        .add_plugin(bevy_tiled::TiledMapPlugin)
+        .add_plugin(bevy_tiled::TiledMapPlugin::default())
         .add_startup_system(setup.system())
         .add_system(camera_movement.system())
         .run();
 }
 
 fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    // Respawn the map whenever it is saved in Tiled.
+    asset_server.watch_for_changes().unwrap();
+
     commands
         .spawn(bevy_tiled::TiledMapComponents {
             map_asset: asset_server.load("assets/ortho-map.tmx").unwrap(),
@@ -0,0 +1,78 @@
+use bevy::{prelude::*, render::camera::Camera};
+use bevy_tiled::{Map, TiledMapAnchor};
+
+fn main() {
+    App::build()
+        .add_default_plugins()
+        .add_plugin(bevy_tiled::TiledMapPlugin::default())
+        .add_startup_system(setup.system())
+        .add_system(pick_tiles.system())
+        .run();
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn(bevy_tiled::TiledMapComponents {
+            map_asset: asset_server.load("assets/tiny-map.tmx").unwrap(),
+            origin: Transform::from_scale(4.0),
+            ..Default::default()
+        })
+        .spawn(Camera2dComponents::default());
+}
+
+#[derive(Default)]
+struct CursorState {
+    cursor_moved_reader: EventReader<CursorMoved>,
+    position: Vec2,
+}
+
+/// Prints the tiles under the cursor when the left mouse button is clicked.
+fn pick_tiles(
+    mut state: Local<CursorState>,
+    cursor_moved_events: Res<Events<CursorMoved>>,
+    mouse_button_input: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    maps: Res<Assets<Map>>,
+    mut cameras: Query<(&Camera, &Transform)>,
+    mut map_entities: Query<(&Handle<Map>, &Transform, &TiledMapAnchor)>,
+) {
+    if let Some(event) = state.cursor_moved_reader.latest(&cursor_moved_events) {
+        state.position = event.position;
+    }
+    if !mouse_button_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+
+    // The cursor is measured from the bottom left of the window, while the 2d camera looks at
+    // its center.
+    let window_size = Vec2::new(window.width as f32, window.height as f32);
+    let screen_position = state.position - window_size / 2.0;
+    for (_, camera_transform) in &mut cameras.iter() {
+        let world = camera_transform
+            .value()
+            .transform_point3(screen_position.extend(0.0));
+
+        for (map_handle, origin, anchor) in &mut map_entities.iter() {
+            let map = match maps.get(map_handle) {
+                Some(map) => map,
+                None => continue,
+            };
+            // Tile positions are relative to the transform the map is drawn with.
+            let map_transform = map.anchor_transform(*origin, *anchor);
+            let position = map_transform.value().inverse().transform_point3(world);
+            let position = Vec2::new(position.x(), position.y());
+
+            match map.world_to_tile(position) {
+                Some((x, y)) => println!("tile ({}, {}):", x, y),
+                None => println!("outside of the map"),
+            }
+            for (layer_index, gid) in map.pick_tile(position) {
+                println!("  {}: gid {}", map.layers[layer_index].name, gid);
+            }
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use bevy::{
+    prelude::*,
+    render::mesh::{VertexAttribute, VertexAttributeValues},
+};
+use glam::Vec4;
+
+/// A tile's animation from its tileset.
+///
+/// Like in Tiled, every instance of an animated tile plays the animation in sync.
+#[derive(Debug, Clone)]
+pub struct TiledTileAnimation {
+    /// The local tile id and duration in milliseconds of each frame.
+    pub frames: Vec<(u32, u32)>,
+    /// The rect of each frame's tile in the tileset image.
+    pub frame_uvs: Vec<Vec4>,
+}
+
+impl TiledTileAnimation {
+    /// Returns the index of the frame shown `time_ms` milliseconds into the looping animation.
+    pub fn frame_at(&self, time_ms: u64) -> usize {
+        let total_duration = self
+            .frames
+            .iter()
+            .map(|(_, duration)| *duration as u64)
+            .sum::<u64>();
+        if total_duration == 0 {
+            return 0;
+        }
+
+        let mut time_ms = time_ms % total_duration;
+        for (index, (_, duration)) in self.frames.iter().enumerate() {
+            let duration = *duration as u64;
+            if time_ms < duration {
+                return index;
+            }
+            time_ms -= duration;
+        }
+        0
+    }
+}
+
+/// An animated tile within a chunk mesh.
+#[derive(Debug, Clone)]
+pub struct AnimatedTile {
+    /// The index of the tile's first vertex in the mesh.
+    pub vertex_index: usize,
+    pub animation: TiledTileAnimation,
+    /// The corner UVs of each frame, with the tile's flips applied.
+    pub frame_corner_uvs: Vec<[[f32; 2]; 4]>,
+    pub current_frame: usize,
+}
+
+/// The animated tiles of a chunk entity's mesh.
+#[derive(Debug, Default)]
+pub struct AnimatedTiles(pub Vec<AnimatedTile>);
+
+/// Advances animated tiles, updating the UVs of their chunk meshes when a frame changes.
+pub fn animate_tiles(
+    time: Res<Time>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut query: Query<(&mut AnimatedTiles, &Handle<Mesh>)>,
+) {
+    let time_ms = (time.seconds_since_startup * 1000.0) as u64;
+    for (mut animated_tiles, mesh_handle) in &mut query.iter() {
+        let has_changed = animated_tiles
+            .0
+            .iter()
+            .any(|tile| tile.animation.frame_at(time_ms) != tile.current_frame);
+        if !has_changed {
+            continue;
+        }
+
+        // Only borrow the mesh mutably once a frame changed, as that re-uploads it.
+        let mesh = match meshes.get_mut(mesh_handle) {
+            Some(mesh) => mesh,
+            None => continue,
+        };
+        let uvs = mesh
+            .attributes
+            .iter_mut()
+            .find(|attribute| attribute.name == VertexAttribute::UV)
+            .and_then(|attribute| match &mut attribute.values {
+                VertexAttributeValues::Float2(uvs) => Some(uvs),
+                _ => None,
+            });
+        let uvs = match uvs {
+            Some(uvs) => uvs,
+            None => continue,
+        };
+
+        for tile in animated_tiles.0.iter_mut() {
+            let frame = tile.animation.frame_at(time_ms);
+            if frame == tile.current_frame {
+                continue;
+            }
+            tile.current_frame = frame;
+            uvs[tile.vertex_index..tile.vertex_index + 4]
+                .copy_from_slice(&tile.frame_corner_uvs[frame]);
+        }
+    }
+}
+
+/// An animated tile object drawn from its tileset's texture atlas.
+#[derive(Debug, Clone)]
+pub struct AnimatedTileSprite {
+    pub animation: TiledTileAnimation,
+    pub current_frame: usize,
+}
+
+/// Advances animated tile objects, pointing their sprites at the current frame's tile.
+///
+/// Only the atlas index changes, so the flips and rotation in the sprite's transform are kept.
+pub fn animate_tile_sprites(
+    time: Res<Time>,
+    mut query: Query<(&mut AnimatedTileSprite, &mut TextureAtlasSprite)>,
+) {
+    let time_ms = (time.seconds_since_startup * 1000.0) as u64;
+    for (mut animated_sprite, mut sprite) in &mut query.iter() {
+        let frame = animated_sprite.animation.frame_at(time_ms);
+        if frame == animated_sprite.current_frame {
+            continue;
+        }
+        animated_sprite.current_frame = frame;
+        sprite.index = animated_sprite.animation.frames[frame].0;
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{element::Element, Map};
+use anyhow::Result;
+use std::path::PathBuf;
+
+/// Builds a `Map` in code, for maps generated at runtime.
+///
+/// The map is written as a TMX document and loaded like a map file, so it is checked and drawn
+/// the same way. Add the built map to `Assets<Map>` and spawn a `TiledMapComponents` with its
+/// handle to draw it.
+#[derive(Debug, Clone)]
+pub struct TiledMapBuilder {
+    orientation: tiled::Orientation,
+    width: u32,
+    height: u32,
+    tile_width: u32,
+    tile_height: u32,
+    next_gid: u32,
+    tilesets: Vec<Element>,
+    layers: Vec<Element>,
+}
+
+impl TiledMapBuilder {
+    /// Starts an orthogonal map of `width` by `height` tiles, with cells of `tile_width` by
+    /// `tile_height` pixels.
+    pub fn new(width: u32, height: u32, tile_width: u32, tile_height: u32) -> Self {
+        Self {
+            orientation: tiled::Orientation::Orthogonal,
+            width,
+            height,
+            tile_width,
+            tile_height,
+            next_gid: 1,
+            tilesets: Vec::new(),
+            layers: Vec::new(),
+        }
+    }
+
+    pub fn orientation(&mut self, orientation: tiled::Orientation) -> &mut Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// Adds a tileset cut from an image of `image_width` by `image_height` pixels into tiles of
+    /// the map's tile size, and returns its first gid. The gids of its tiles count up from there
+    /// row by row.
+    ///
+    /// `image` is an asset path, like the ones passed to `AssetServer::load`.
+    pub fn add_tileset(
+        &mut self,
+        name: &str,
+        image: impl Into<PathBuf>,
+        image_width: u32,
+        image_height: u32,
+    ) -> u32 {
+        let columns = image_width / self.tile_width;
+        let rows = image_height / self.tile_height;
+        let mut tileset = Element {
+            name: "tileset".to_string(),
+            ..Default::default()
+        };
+        tileset.set_attribute("firstgid", self.next_gid.to_string());
+        tileset.set_attribute("name", name.to_string());
+        tileset.set_attribute("tilewidth", self.tile_width.to_string());
+        tileset.set_attribute("tileheight", self.tile_height.to_string());
+        tileset.set_attribute("tilecount", (columns * rows).to_string());
+        tileset.set_attribute("columns", columns.to_string());
+        let mut image_element = Element {
+            name: "image".to_string(),
+            ..Default::default()
+        };
+        image_element.set_attribute("source", image.into().to_string_lossy().into_owned());
+        image_element.set_attribute("width", image_width.to_string());
+        image_element.set_attribute("height", image_height.to_string());
+        tileset.children.push(image_element);
+        self.tilesets.push(tileset);
+
+        let first_gid = self.next_gid;
+        self.next_gid += (columns * rows).max(1);
+        first_gid
+    }
+
+    /// Adds a tile layer above the previous ones. `gids` lists the layer's tiles row by row,
+    /// with 0 for empty cells and Tiled's flip flags in the top bits.
+    pub fn add_layer(&mut self, name: &str, gids: &[u32]) -> &mut Self {
+        let mut layer = Element {
+            name: "layer".to_string(),
+            ..Default::default()
+        };
+        layer.set_attribute("id", (self.layers.len() + 1).to_string());
+        layer.set_attribute("name", name.to_string());
+        layer.set_attribute("width", self.width.to_string());
+        layer.set_attribute("height", self.height.to_string());
+        let mut data = Element {
+            name: "data".to_string(),
+            ..Default::default()
+        };
+        data.set_attribute("encoding", "csv".to_string());
+        data.text = gids
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        layer.children.push(data);
+        self.layers.push(layer);
+        self
+    }
+
+    /// Builds the map, failing like a map file would if a layer doesn't have a tile for every
+    /// cell.
+    pub fn build(&self) -> Result<Map> {
+        let mut map = Element {
+            name: "map".to_string(),
+            ..Default::default()
+        };
+        let orientation = match self.orientation {
+            tiled::Orientation::Orthogonal => "orthogonal",
+            tiled::Orientation::Isometric => "isometric",
+            tiled::Orientation::Staggered => "staggered",
+            tiled::Orientation::Hexagonal => "hexagonal",
+        };
+        map.set_attribute("version", "1.4".to_string());
+        map.set_attribute("orientation", orientation.to_string());
+        map.set_attribute("width", self.width.to_string());
+        map.set_attribute("height", self.height.to_string());
+        map.set_attribute("tilewidth", self.tile_width.to_string());
+        map.set_attribute("tileheight", self.tile_height.to_string());
+        map.set_attribute("nextlayerid", (self.layers.len() + 1).to_string());
+        map.children.extend(self.tilesets.iter().cloned());
+        map.children.extend(self.layers.iter().cloned());
+
+        // Images are resolved relative to the map's directory, which is the asset folder here.
+        Ok(Map::from_bytes("generated.tmx", map.to_bytes()?)?)
+    }
+}
@@ -0,0 +1,144 @@
+use crate::TileGrid;
+use bevy::math::Vec2;
+
+/// A rectangle of map cells, measured in tiles from the top left of the map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Spawns colliders for the non-empty tiles of a tile layer when `layer` names one, such as a
+/// `Collision` layer.
+///
+/// With `merge` enabled, neighboring tiles are joined into larger rectangles so that fewer
+/// colliders are spawned. Tiles are only merged on orthogonal maps, where rectangles of tiles
+/// are still boxes in the world.
+#[derive(Debug, Clone, Default)]
+pub struct TiledMapColliders {
+    pub layer: Option<String>,
+    pub merge: bool,
+}
+
+/// An axis-aligned box collider spawned for `TiledMapColliders`, centered on its entity's
+/// transform.
+///
+/// The crate doesn't depend on a physics engine, so add a collider of `size` to the entity in
+/// your own system to use it with one.
+#[derive(Debug, Clone)]
+pub struct TiledCollider {
+    /// The cells covered by the collider.
+    pub rect: TileRect,
+    /// The size of the box in world units.
+    pub size: Vec2,
+}
+
+/// Covers the non-empty cells of `grid` with rectangles.
+///
+/// Rectangles are grown greedily from the top left: first as far right along their row as the
+/// cells allow, then down for as long as the rows below are filled across the whole width.
+pub(crate) fn merge_tile_rects(grid: TileGrid<'_>) -> Vec<TileRect> {
+    let (width, height) = (grid.width(), grid.height());
+    let filled = |x, y| grid.get(x, y).is_some_and(|gid| gid != 0);
+    let mut covered = vec![false; (width * height) as usize];
+    let mut rects = Vec::new();
+
+    for y in 0..height {
+        for x in 0..width {
+            if covered[(y * width + x) as usize] || !filled(x, y) {
+                continue;
+            }
+            let free = |x, y| filled(x, y) && !covered[(y * width + x) as usize];
+            let rect_width = (x..width).take_while(|&x| free(x, y)).count() as u32;
+            let rect_height = 1
+                + (y + 1..height)
+                    .take_while(|&below| (x..x + rect_width).all(|x| free(x, below)))
+                    .count() as u32;
+            for covered_y in y..y + rect_height {
+                for covered_x in x..x + rect_width {
+                    covered[(covered_y * width + covered_x) as usize] = true;
+                }
+            }
+            rects.push(TileRect {
+                x,
+                y,
+                width: rect_width,
+                height: rect_height,
+            });
+        }
+    }
+
+    rects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the rectangles merged from rows of cells, where `#` is a filled cell.
+    fn merge(rows: &[&str]) -> Vec<TileRect> {
+        let rows = rows
+            .iter()
+            .map(|row| {
+                row.chars()
+                    .map(|cell| tiled::LayerTile::new(if cell == '#' { 1 } else { 0 }))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let width = rows.first().map_or(0, |row| row.len()) as u32;
+        merge_tile_rects(TileGrid::new(&rows, width, rows.len() as u32))
+    }
+
+    fn rect(x: u32, y: u32, width: u32, height: u32) -> TileRect {
+        TileRect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn filled_rectangle_is_one_rect() {
+        assert_eq!(merge(&["###", "###"]), [rect(0, 0, 3, 2)]);
+    }
+
+    #[test]
+    fn l_shape_is_split_where_rows_get_narrower() {
+        assert_eq!(
+            merge(&["#..", "#..", "###"]),
+            [rect(0, 0, 1, 3), rect(1, 2, 2, 1)]
+        );
+        assert_eq!(
+            merge(&["###", "#..", "#.."]),
+            [rect(0, 0, 3, 1), rect(0, 1, 1, 2)]
+        );
+    }
+
+    #[test]
+    fn holes_are_left_uncovered() {
+        let rects = merge(&["###", "#.#", "###"]);
+        assert_eq!(
+            rects,
+            [
+                rect(0, 0, 3, 1),
+                rect(0, 1, 1, 2),
+                rect(2, 1, 1, 2),
+                rect(1, 2, 1, 1)
+            ]
+        );
+        let covered = rects
+            .iter()
+            .map(|rect| rect.width * rect.height)
+            .sum::<u32>();
+        assert_eq!(covered, 8);
+    }
+
+    #[test]
+    fn empty_map_has_no_rects() {
+        assert_eq!(merge(&["...", "..."]), []);
+        assert_eq!(merge(&[]), []);
+    }
+}
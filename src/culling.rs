@@ -0,0 +1,168 @@
+use bevy::{
+    prelude::*,
+    render::{
+        camera::{Camera, OrthographicProjection},
+        render_graph::base::camera::CAMERA2D,
+    },
+};
+use glam::Vec2;
+
+/// The bounds of a chunk entity's mesh relative to the entity's transform.
+///
+/// Chunks whose bounds are outside of the 2d camera's view aren't drawn.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RenderChunk {
+    pub min: Vec2,
+    pub max: Vec2,
+    /// Whether the chunk's layer is hidden in the map or with `TiledLayerVisibility`.
+    pub hidden: bool,
+}
+
+impl RenderChunk {
+    /// Returns the bounds of a mesh's vertex positions.
+    pub fn from_positions(positions: &[[f32; 3]]) -> Self {
+        let mut positions = positions
+            .iter()
+            .map(|position| Vec2::new(position[0], position[1]));
+        let first = match positions.next() {
+            Some(first) => first,
+            None => return Self::default(),
+        };
+        let (min, max) = positions.fold((first, first), |(min, max), position| {
+            (min.min(position), max.max(position))
+        });
+        Self {
+            min,
+            max,
+            hidden: false,
+        }
+    }
+
+    /// Returns the bounds after applying `transform`.
+    pub fn transformed(&self, transform: &Mat4) -> Self {
+        bounds_of_corners(self.min, self.max, transform)
+    }
+
+    pub fn intersects(&self, other: &RenderChunk) -> bool {
+        self.min.x() <= other.max.x()
+            && other.min.x() <= self.max.x()
+            && self.min.y() <= other.max.y()
+            && other.min.y() <= self.max.y()
+    }
+}
+
+fn bounds_of_corners(min: Vec2, max: Vec2, transform: &Mat4) -> RenderChunk {
+    let corners = [
+        [min.x(), min.y(), 0.0],
+        [min.x(), max.y(), 0.0],
+        [max.x(), max.y(), 0.0],
+        [max.x(), min.y(), 0.0],
+    ];
+    let corners = corners
+        .iter()
+        .map(|corner| {
+            let corner = transform.transform_point3(Vec3::from(*corner));
+            [corner.x(), corner.y(), corner.z()]
+        })
+        .collect::<Vec<_>>();
+    RenderChunk::from_positions(&corners)
+}
+
+/// Hides chunk entities outside of the 2d camera's view, and shows them again once they are in
+/// view. Chunks of hidden layers stay hidden.
+pub fn cull_chunks(
+    mut cameras: Query<(&Camera, &OrthographicProjection, &Transform)>,
+    mut query: Query<(&RenderChunk, &Transform, &mut Draw)>,
+) {
+    let mut view = None;
+    for (camera, projection, transform) in &mut cameras.iter() {
+        if camera.name.as_deref() == Some(CAMERA2D) {
+            view = Some(bounds_of_corners(
+                Vec2::new(projection.left, projection.bottom),
+                Vec2::new(projection.right, projection.top),
+                transform.value(),
+            ));
+        }
+    }
+    let view = match view {
+        Some(view) => view,
+        None => return,
+    };
+
+    for (render_chunk, transform, mut draw) in &mut query.iter() {
+        let is_visible = !render_chunk.hidden
+            && render_chunk
+                .transformed(transform.value())
+                .intersects(&view);
+        if draw.is_visible != is_visible {
+            draw.is_visible = is_visible;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{apply_layer_visibility, Map, TileMapChunk, TiledLayerVisibility, TiledMapEntity};
+    use bevy::ecs::Schedule;
+
+    #[test]
+    fn hidden_layers_stay_hidden_in_view() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(Assets::<Map>::default());
+        let map_entity = TiledMapEntity {
+            map: Handle::new(),
+            entity: Entity::new(0),
+        };
+        world.spawn((
+            Camera {
+                name: Some(CAMERA2D.to_string()),
+                ..Default::default()
+            },
+            OrthographicProjection {
+                left: -100.0,
+                right: 100.0,
+                bottom: -100.0,
+                top: 100.0,
+                ..Default::default()
+            },
+            Transform::identity(),
+        ));
+        let chunk = |layer_id| {
+            (
+                TileMapChunk {
+                    color: Color::WHITE,
+                    layer_id,
+                },
+                map_entity,
+                RenderChunk {
+                    min: Vec2::new(-10.0, -10.0),
+                    max: Vec2::new(10.0, 10.0),
+                    hidden: false,
+                },
+                Transform::identity(),
+                Draw::default(),
+            )
+        };
+        let hidden = world.spawn(chunk(0.0));
+        let shown = world.spawn(chunk(1.0));
+        world.spawn((
+            TiledLayerVisibility {
+                layer_index: 0,
+                visible: false,
+            },
+            map_entity,
+        ));
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update");
+        schedule.add_system_to_stage("update", apply_layer_visibility.system());
+        schedule.add_system_to_stage("update", cull_chunks.system());
+        for _ in 0..2 {
+            schedule.run(&mut world, &mut resources);
+            assert!(!world.get::<Draw>(hidden).unwrap().is_visible);
+            assert!(world.get::<Draw>(shown).unwrap().is_visible);
+        }
+    }
+}
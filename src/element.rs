@@ -0,0 +1,108 @@
+use crate::TiledError;
+use std::str::FromStr;
+use xml::{
+    reader::{self, EventReader},
+    writer::{self, EmitterConfig},
+};
+
+/// An element of a TMX or TSX document.
+///
+/// Used to read the parts of a document the `tiled` crate skips, and to rewrite documents into a
+/// form it can parse.
+#[derive(Debug, Clone, Default)]
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Element>,
+    /// The text content of the element, such as a layer's encoded tile data.
+    pub text: String,
+}
+
+impl Element {
+    /// Parses a document and returns its root element.
+    pub fn parse(bytes: &[u8]) -> Result<Element, TiledError> {
+        let mut stack: Vec<Element> = Vec::new();
+        for event in EventReader::new(bytes) {
+            match event.map_err(|error| TiledError::Xml(error.to_string()))? {
+                reader::XmlEvent::StartElement {
+                    name, attributes, ..
+                } => stack.push(Element {
+                    name: name.local_name,
+                    attributes: attributes
+                        .into_iter()
+                        .map(|attribute| (attribute.name.local_name, attribute.value))
+                        .collect(),
+                    ..Default::default()
+                }),
+                reader::XmlEvent::EndElement { .. } => {
+                    let element = stack.pop().expect("end element without a start element");
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(element),
+                        None => return Ok(element),
+                    }
+                }
+                reader::XmlEvent::Characters(text) | reader::XmlEvent::CData(text) => {
+                    if let Some(element) = stack.last_mut() {
+                        element.text.push_str(&text);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Err(TiledError::Xml("document has no root element".to_string()))
+    }
+
+    /// Writes the element as a document.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, TiledError> {
+        let mut bytes = Vec::new();
+        let mut writer = EmitterConfig::new().create_writer(&mut bytes);
+        self.write(&mut writer)
+            .map_err(|error| TiledError::Xml(error.to_string()))?;
+        Ok(bytes)
+    }
+
+    fn write(&self, writer: &mut writer::EventWriter<&mut Vec<u8>>) -> writer::Result<()> {
+        let mut start_element = writer::XmlEvent::start_element(self.name.as_str());
+        for (name, value) in self.attributes.iter() {
+            start_element = start_element.attr(name.as_str(), value.as_str());
+        }
+        writer.write(start_element)?;
+        if !self.text.is_empty() {
+            writer.write(writer::XmlEvent::characters(&self.text))?;
+        }
+        for child in self.children.iter() {
+            child.write(writer)?;
+        }
+        writer.write(writer::XmlEvent::end_element())?;
+        Ok(())
+    }
+
+    /// Returns the parsed value of an attribute, or `None` if it is missing or doesn't parse.
+    pub fn attribute<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.attributes
+            .iter()
+            .find(|(attribute, _)| attribute == name)
+            .and_then(|(_, value)| value.parse().ok())
+    }
+
+    pub fn set_attribute(&mut self, name: &str, value: String) {
+        match self
+            .attributes
+            .iter_mut()
+            .find(|(attribute, _)| attribute == name)
+        {
+            Some((_, existing)) => *existing = value,
+            None => self.attributes.push((name.to_string(), value)),
+        }
+    }
+
+    /// Returns the first child element with the given name.
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    /// Iterates the child elements with the given name.
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+}
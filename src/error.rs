@@ -0,0 +1,143 @@
+use std::{
+    error::Error,
+    fmt, io,
+    num::ParseIntError,
+    path::{Path, PathBuf},
+    string::FromUtf8Error,
+};
+
+/// Why a map couldn't be loaded, returned by `Map::from_bytes`.
+///
+/// Maps loaded by the asset server log the error instead, with the map's path.
+#[derive(Debug)]
+pub enum TiledError {
+    /// The document isn't well-formed XML, or ends early.
+    Xml(String),
+    /// The document isn't valid JSON, or isn't shaped like a map Tiled exported.
+    Json(String),
+    /// An element is missing an attribute it needs, or the attribute has the wrong type.
+    MissingAttribute(String),
+    /// A layer's tile data uses an unknown encoding or compression, or doesn't decompress.
+    BadEncoding(String),
+    /// The document isn't valid UTF-8.
+    Utf8(FromUtf8Error),
+    /// A layer's base64 tile data doesn't decode.
+    Base64(base64::DecodeError),
+    /// A gid in a layer's CSV tile data isn't a number.
+    IntParse(ParseIntError),
+    /// A tile layer doesn't have a tile for every cell of the map, as can happen after resizing
+    /// it by hand.
+    LayerSizeMismatch {
+        layer: String,
+        expected: usize,
+        found: usize,
+    },
+    /// The `tiled` parser found a different number of image layers than the map has, so their
+    /// attributes can't be matched up.
+    ImageLayerCountMismatch { expected: usize, found: usize },
+    /// A tileset has neither an image nor tiles with images.
+    MissingImage { tileset: String },
+    /// The image embedded in a tileset doesn't decode.
+    InvalidImage { tileset: String, reason: String },
+    /// An object's template can't be used, such as one with a tile from a tileset the map
+    /// doesn't have.
+    InvalidTemplate { path: PathBuf, reason: String },
+    /// The map uses something that isn't supported, such as being infinite.
+    Unsupported(String),
+    /// The map's path can't be used to find the files it refers to.
+    InvalidPath(PathBuf),
+    /// A file the map refers to, such as an external tileset or a template, couldn't be read.
+    Io { path: PathBuf, error: io::Error },
+    /// A file the map refers to couldn't be parsed.
+    File {
+        path: PathBuf,
+        error: Box<TiledError>,
+    },
+}
+
+impl TiledError {
+    /// Wraps an error from parsing the file at `path`.
+    pub(crate) fn in_file(path: &Path) -> impl FnOnce(TiledError) -> TiledError + '_ {
+        move |error| TiledError::File {
+            path: path.to_path_buf(),
+            error: Box::new(error),
+        }
+    }
+}
+
+impl fmt::Display for TiledError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TiledError::Xml(message) => write!(f, "invalid XML: {}", message),
+            TiledError::Json(message) => write!(f, "invalid JSON: {}", message),
+            TiledError::MissingAttribute(message) => f.write_str(message),
+            TiledError::BadEncoding(message) => write!(f, "invalid tile data: {}", message),
+            TiledError::Utf8(error) => write!(f, "invalid UTF-8: {}", error),
+            TiledError::Base64(error) => write!(f, "invalid base64 tile data: {}", error),
+            TiledError::IntParse(error) => write!(f, "invalid CSV tile data: {}", error),
+            TiledError::LayerSizeMismatch {
+                layer,
+                expected,
+                found,
+            } => write!(
+                f,
+                "layer {:?} has {} tiles, but the map needs {}",
+                layer, found, expected
+            ),
+            TiledError::ImageLayerCountMismatch { expected, found } => write!(
+                f,
+                "the map has {} image layers, but {} were parsed",
+                expected, found
+            ),
+            TiledError::MissingImage { tileset } => {
+                write!(f, "tileset {:?} has no image", tileset)
+            }
+            TiledError::InvalidImage { tileset, reason } => {
+                write!(
+                    f,
+                    "the image embedded in tileset {:?} is invalid: {}",
+                    tileset, reason
+                )
+            }
+            TiledError::InvalidTemplate { path, reason } => {
+                write!(f, "template {:?} {}", path, reason)
+            }
+            TiledError::Unsupported(message) => f.write_str(message),
+            TiledError::InvalidPath(path) => write!(f, "invalid map path {:?}", path),
+            TiledError::Io { path, error } => write!(f, "failed to read {:?}: {}", path, error),
+            TiledError::File { path, error } => write!(f, "in {:?}: {}", path, error),
+        }
+    }
+}
+
+impl Error for TiledError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TiledError::Utf8(error) => Some(error),
+            TiledError::Base64(error) => Some(error),
+            TiledError::IntParse(error) => Some(error),
+            TiledError::Io { error, .. } => Some(error),
+            TiledError::File { error, .. } => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<tiled::TiledError> for TiledError {
+    fn from(error: tiled::TiledError) -> Self {
+        match error {
+            tiled::TiledError::MalformedAttributes(message) => {
+                TiledError::MissingAttribute(message)
+            }
+            tiled::TiledError::DecompressingError(error) => {
+                TiledError::BadEncoding(error.to_string())
+            }
+            tiled::TiledError::Base64DecodingError(error) => TiledError::Base64(error),
+            tiled::TiledError::XmlDecodingError(error) => TiledError::Xml(error.to_string()),
+            tiled::TiledError::PrematureEnd(message) => TiledError::Xml(message),
+            // Properties and external tilesets are handled before `tiled` parses the map, so
+            // its remaining errors are about tile data.
+            tiled::TiledError::Other(message) => TiledError::BadEncoding(message),
+        }
+    }
+}
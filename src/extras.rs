@@ -0,0 +1,698 @@
+use crate::{
+    element::Element, json, GridOrientation, HexLayout, LayerKind, PropertyValue, RenderOrder,
+    StaggerAxis, StaggerIndex, TextHAlign, TextVAlign, TiledDrawOrder, TiledError, TiledGrid,
+    TiledGroupLayer, TiledObjectText, TiledProperties, TiledTransformations, TiledWangColor,
+    TiledWangSet, WangId, WangSetType,
+};
+use bevy::render::color::Color;
+use glam::Vec2;
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+};
+
+/// Parts of a TMX document that the `tiled` crate reads past without keeping.
+#[derive(Debug, Default)]
+pub struct MapExtras {
+    pub class: Option<String>,
+    pub tiled_version: Option<String>,
+    pub next_layer_id: Option<u32>,
+    pub next_object_id: Option<u32>,
+    pub editor_chunk_size: Option<(u32, u32)>,
+    pub background_color: Option<Color>,
+    pub hex_layout: HexLayout,
+    pub render_order: RenderOrder,
+    pub properties: TiledProperties,
+    /// The attributes of each `<layer>`, in the order `tiled` lists them in `Map::layers`.
+    pub layers: Vec<LayerExtras>,
+    /// The attributes of each `<objectgroup>`, in the order `tiled` lists them in
+    /// `Map::object_groups`.
+    pub object_layers: Vec<ObjectLayerExtras>,
+    /// The map's layers as they are nested in groups.
+    pub layer_tree: Vec<LayerKind>,
+    /// The attributes of each `<imagelayer>`, in the order `tiled` lists them in
+    /// `Map::image_layers`.
+    pub image_layers: Vec<ImageLayerExtras>,
+    /// The attributes of each object, keyed by object id.
+    pub objects: HashMap<u32, ObjectExtras>,
+    /// The file each tileset was defined in keyed by first gid. Embedded tilesets use the map's
+    /// path.
+    pub tileset_paths: HashMap<u32, PathBuf>,
+    /// The attributes of each tileset, keyed by first gid.
+    pub tilesets: HashMap<u32, TilesetExtras>,
+    /// The root elements of external tilesets, keyed by first gid.
+    external_tilesets: HashMap<u32, Element>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LayerExtras {
+    pub id: Option<u32>,
+    pub class: Option<String>,
+    /// The layer's offset in pixels, with y pointing down, including the offsets of its groups.
+    pub offset: Vec2,
+    /// The layer's tint color, multiplied with the tints of its groups.
+    pub tint_color: Option<Color>,
+    /// The layer's parallax factor, multiplied with the factors of its groups.
+    pub parallax: Vec2,
+    pub properties: TiledProperties,
+    /// Whether all of the layer's groups are visible.
+    pub group_visible: bool,
+    /// The combined opacity of the layer's groups.
+    pub group_opacity: f32,
+}
+
+impl Default for LayerExtras {
+    fn default() -> Self {
+        Self {
+            id: None,
+            class: None,
+            offset: Vec2::zero(),
+            tint_color: None,
+            parallax: Vec2::one(),
+            properties: TiledProperties::new(),
+            group_visible: true,
+            group_opacity: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ObjectLayerExtras {
+    pub id: Option<u32>,
+    pub class: Option<String>,
+    pub draw_order: TiledDrawOrder,
+    pub properties: TiledProperties,
+    /// Whether all of the layer's groups are visible.
+    pub group_visible: bool,
+    /// The combined opacity of the layer's groups.
+    pub group_opacity: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImageLayerExtras {
+    pub id: Option<u32>,
+    pub class: Option<String>,
+    /// The layer's offset in pixels, with y pointing down, including the offsets of its groups.
+    pub offset: Vec2,
+    /// The layer's tint color, multiplied with the tints of its groups.
+    pub tint_color: Option<Color>,
+    pub repeat_x: bool,
+    pub repeat_y: bool,
+    pub properties: TiledProperties,
+    /// How many tile layers come before this layer.
+    pub tile_layers_before: usize,
+    /// Whether all of the layer's groups are visible.
+    pub group_visible: bool,
+    /// The combined opacity of the layer's groups.
+    pub group_opacity: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct ObjectExtras {
+    pub class: Option<String>,
+    pub properties: TiledProperties,
+    /// The text of a text object.
+    pub text: Option<TiledObjectText>,
+    /// Read here since `tiled` expects `true` or `false` instead of the `0` Tiled writes.
+    pub visible: bool,
+}
+
+/// What a layer inherits from the groups it is nested in.
+#[derive(Debug, Clone, Copy)]
+struct GroupInheritance {
+    visible: bool,
+    opacity: f32,
+    /// The summed offset of the groups in pixels, with y pointing down.
+    offset: Vec2,
+    /// The product of the groups' tint colors.
+    tint_color: Option<Color>,
+    /// The product of the groups' parallax factors.
+    parallax: Vec2,
+}
+
+impl Default for GroupInheritance {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            opacity: 1.0,
+            offset: Vec2::zero(),
+            tint_color: None,
+            parallax: Vec2::one(),
+        }
+    }
+}
+
+/// Multiplies a layer's tint color with the tint it inherits from its groups, like Tiled does.
+fn combine_tints(inherited: Option<Color>, own: Option<Color>) -> Option<Color> {
+    match (inherited, own) {
+        (Some(inherited), Some(own)) => Some(Color::rgba(
+            inherited.r * own.r,
+            inherited.g * own.g,
+            inherited.b * own.b,
+            inherited.a * own.a,
+        )),
+        (inherited, own) => inherited.or(own),
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct TilesetExtras {
+    /// The offset tiles are drawn at in pixels, with y pointing down.
+    pub tile_offset: Vec2,
+    pub grid: Option<TiledGrid>,
+    pub transformations: TiledTransformations,
+    pub wang_sets: Vec<TiledWangSet>,
+    pub properties: TiledProperties,
+    /// The class of each tile that has one, keyed by local tile id.
+    pub tile_classes: HashMap<u32, String>,
+    /// The properties of each tile, keyed by local tile id.
+    pub tile_properties: HashMap<u32, TiledProperties>,
+    /// The properties of the objects in each tile's collision shapes, keyed by local tile id
+    /// and object id.
+    pub tile_object_properties: HashMap<(u32, u32), TiledProperties>,
+    /// The tileset's image, if it is stored in the tileset instead of a file.
+    pub embedded_image: Option<EmbeddedImage>,
+}
+
+/// An image stored in a tileset's `<image>` element instead of a file.
+#[derive(Debug, Default, Clone)]
+pub struct EmbeddedImage {
+    /// The image's file format, such as `png`.
+    pub format: Option<String>,
+    /// The image's bytes, encoded as base64.
+    pub data: String,
+}
+
+impl TilesetExtras {
+    /// Reads a `<tileset>` element defined in the file at `path`.
+    fn parse(tileset: &Element, path: &Path) -> TilesetExtras {
+        let mut extras = TilesetExtras {
+            properties: parse_properties(tileset, path),
+            embedded_image: tileset.child("image").and_then(|image| {
+                Some(EmbeddedImage {
+                    format: image.attribute("format"),
+                    data: image.child("data")?.text.clone(),
+                })
+            }),
+            ..Default::default()
+        };
+        if let Some(tile_offset) = tileset.child("tileoffset") {
+            extras.tile_offset = Vec2::new(
+                tile_offset.attribute("x").unwrap_or(0.0),
+                tile_offset.attribute("y").unwrap_or(0.0),
+            );
+        }
+        if let Some(grid) = tileset.child("grid") {
+            let orientation = match grid.attribute::<String>("orientation").as_deref() {
+                Some("isometric") => GridOrientation::Isometric,
+                _ => GridOrientation::Orthogonal,
+            };
+            if let (Some(width), Some(height)) = (grid.attribute("width"), grid.attribute("height"))
+            {
+                extras.grid = Some(TiledGrid {
+                    orientation,
+                    size: Vec2::new(width, height),
+                });
+            }
+        }
+        if let Some(transformations) = tileset.child("transformations") {
+            let flag = |name| transformations.attribute::<u8>(name).unwrap_or(0) != 0;
+            extras.transformations = TiledTransformations {
+                hflip: flag("hflip"),
+                vflip: flag("vflip"),
+                rotate: flag("rotate"),
+                prefer_untransformed: flag("preferuntransformed"),
+            };
+        }
+        extras.wang_sets = tileset
+            .children_named("wangsets")
+            .flat_map(|wang_sets| wang_sets.children_named("wangset"))
+            .map(|wang_set| parse_wang_set(wang_set, path))
+            .collect();
+        for tile in tileset.children_named("tile") {
+            let tile_id = match tile.attribute("id") {
+                Some(tile_id) => tile_id,
+                None => continue,
+            };
+            if let Some(class) = parse_class(tile) {
+                extras.tile_classes.insert(tile_id, class);
+            }
+            let properties = parse_properties(tile, path);
+            if !properties.is_empty() {
+                extras.tile_properties.insert(tile_id, properties);
+            }
+            for object in tile
+                .children_named("objectgroup")
+                .flat_map(|object_group| object_group.children_named("object"))
+            {
+                let properties = parse_properties(object, path);
+                if let (Some(object_id), false) = (object.attribute("id"), properties.is_empty()) {
+                    extras
+                        .tile_object_properties
+                        .insert((tile_id, object_id), properties);
+                }
+            }
+        }
+        extras
+    }
+}
+
+impl MapExtras {
+    pub fn parse(map: &Element, map_path: &Path) -> Result<MapExtras, TiledError> {
+        let mut extras = MapExtras {
+            class: parse_class(map),
+            tiled_version: map.attribute("tiledversion"),
+            next_layer_id: map.attribute("nextlayerid"),
+            next_object_id: map.attribute("nextobjectid"),
+            editor_chunk_size: map
+                .child("editorsettings")
+                .and_then(|settings| settings.child("chunksize"))
+                .and_then(|chunk_size| {
+                    Some((
+                        chunk_size.attribute("width")?,
+                        chunk_size.attribute("height")?,
+                    ))
+                }),
+            background_color: map
+                .attribute::<String>("backgroundcolor")
+                .and_then(|color| parse_color(&color)),
+            properties: parse_properties(map, map_path),
+            ..Default::default()
+        };
+
+        match map.attribute::<String>("renderorder").as_deref() {
+            Some("right-down") => extras.render_order = RenderOrder::RightDown,
+            Some("right-up") => extras.render_order = RenderOrder::RightUp,
+            Some("left-down") => extras.render_order = RenderOrder::LeftDown,
+            Some("left-up") => extras.render_order = RenderOrder::LeftUp,
+            _ => {}
+        }
+        // Staggered maps share the hexagonal layout with a side length of 0, ignore a side length
+        // left over from converting a hexagonal map.
+        if map.attribute::<String>("orientation").as_deref() == Some("hexagonal") {
+            if let Some(side_length) = map.attribute("hexsidelength") {
+                extras.hex_layout.side_length = side_length;
+            }
+        }
+        match map.attribute::<String>("staggeraxis").as_deref() {
+            Some("x") => extras.hex_layout.stagger_axis = StaggerAxis::X,
+            Some("y") => extras.hex_layout.stagger_axis = StaggerAxis::Y,
+            _ => {}
+        }
+        match map.attribute::<String>("staggerindex").as_deref() {
+            Some("odd") => extras.hex_layout.stagger_index = StaggerIndex::Odd,
+            Some("even") => extras.hex_layout.stagger_index = StaggerIndex::Even,
+            _ => {}
+        }
+
+        for tileset in map.children_named("tileset") {
+            let first_gid = match tileset.attribute("firstgid") {
+                Some(first_gid) => first_gid,
+                None => continue,
+            };
+            match tileset.attribute::<String>("source") {
+                Some(source) => {
+                    let tileset_path = resolve_path(map_path, &source);
+                    let tileset_bytes =
+                        std::fs::read(&tileset_path).map_err(|error| TiledError::Io {
+                            path: tileset_path.clone(),
+                            error,
+                        })?;
+                    let external_tileset = match tileset_path
+                        .extension()
+                        .and_then(|extension| extension.to_str())
+                    {
+                        Some("tsj") | Some("json") => json::parse_tileset(&tileset_bytes),
+                        _ => Element::parse(&tileset_bytes),
+                    }
+                    .map_err(TiledError::in_file(&tileset_path))?;
+                    extras.tilesets.insert(
+                        first_gid,
+                        TilesetExtras::parse(&external_tileset, &tileset_path),
+                    );
+                    extras.external_tilesets.insert(first_gid, external_tileset);
+                    extras.tileset_paths.insert(first_gid, tileset_path);
+                }
+                None => {
+                    extras
+                        .tilesets
+                        .insert(first_gid, TilesetExtras::parse(tileset, map_path));
+                    extras
+                        .tileset_paths
+                        .insert(first_gid, map_path.to_path_buf());
+                }
+            }
+        }
+
+        extras.layer_tree = extras.parse_layers(map, map_path, GroupInheritance::default());
+        Ok(extras)
+    }
+
+    /// Reads the layers of the map or a group, in document order like `tiled` does.
+    fn parse_layers(
+        &mut self,
+        parent: &Element,
+        map_path: &Path,
+        inherited: GroupInheritance,
+    ) -> Vec<LayerKind> {
+        let mut layer_tree = Vec::new();
+        for element in parent.children.iter() {
+            match element.name.as_str() {
+                "layer" => {
+                    layer_tree.push(LayerKind::Tile(self.layers.len()));
+                    self.layers.push(LayerExtras {
+                        id: element.attribute("id"),
+                        class: parse_class(element),
+                        offset: inherited.offset + parse_offset(element),
+                        tint_color: combine_tints(inherited.tint_color, parse_tint(element)),
+                        parallax: inherited.parallax * parse_parallax(element),
+                        properties: parse_properties(element, map_path),
+                        group_visible: inherited.visible,
+                        group_opacity: inherited.opacity,
+                    });
+                }
+                "objectgroup" => {
+                    layer_tree.push(LayerKind::Object(self.object_layers.len()));
+                    self.object_layers.push(ObjectLayerExtras {
+                        id: element.attribute("id"),
+                        class: parse_class(element),
+                        draw_order: match element.attribute::<String>("draworder").as_deref() {
+                            Some("index") => TiledDrawOrder::Index,
+                            _ => TiledDrawOrder::TopDown,
+                        },
+                        properties: parse_properties(element, map_path),
+                        group_visible: inherited.visible,
+                        group_opacity: inherited.opacity,
+                    });
+                    for object in element.children_named("object") {
+                        if let Some(id) = object.attribute("id") {
+                            self.objects.insert(
+                                id,
+                                ObjectExtras {
+                                    class: parse_class(object),
+                                    properties: parse_properties(object, map_path),
+                                    text: object.child("text").map(parse_text),
+                                    visible: object.attribute::<u8>("visible").unwrap_or(1) != 0,
+                                },
+                            );
+                        }
+                    }
+                }
+                "imagelayer" => {
+                    layer_tree.push(LayerKind::Image(self.image_layers.len()));
+                    self.image_layers.push(ImageLayerExtras {
+                        id: element.attribute("id"),
+                        class: parse_class(element),
+                        offset: inherited.offset + parse_offset(element),
+                        tint_color: combine_tints(inherited.tint_color, parse_tint(element)),
+                        repeat_x: element.attribute::<u8>("repeatx").unwrap_or(0) != 0,
+                        repeat_y: element.attribute::<u8>("repeaty").unwrap_or(0) != 0,
+                        properties: parse_properties(element, map_path),
+                        tile_layers_before: self.layers.len(),
+                        group_visible: inherited.visible,
+                        group_opacity: inherited.opacity,
+                    });
+                }
+                "group" => {
+                    let visible = element.attribute::<u8>("visible").unwrap_or(1) != 0;
+                    let opacity = element.attribute("opacity").unwrap_or(1.0);
+                    let offset = parse_offset(element);
+                    let tint_color = parse_tint(element);
+                    let parallax = parse_parallax(element);
+                    let children = self.parse_layers(
+                        element,
+                        map_path,
+                        GroupInheritance {
+                            visible: inherited.visible && visible,
+                            opacity: inherited.opacity * opacity,
+                            offset: inherited.offset + offset,
+                            tint_color: combine_tints(inherited.tint_color, tint_color),
+                            parallax: inherited.parallax * parallax,
+                        },
+                    );
+                    layer_tree.push(LayerKind::Group(TiledGroupLayer {
+                        id: element.attribute("id"),
+                        name: element.attribute("name").unwrap_or_default(),
+                        class: parse_class(element),
+                        visible,
+                        opacity,
+                        offset,
+                        tint_color,
+                        parallax,
+                        properties: parse_properties(element, map_path),
+                        children,
+                    }));
+                }
+                _ => {}
+            }
+        }
+        layer_tree
+    }
+
+    /// Rewrites the map into a document the `tiled` crate can parse.
+    ///
+    /// External tilesets are embedded, since `tiled` would otherwise read them itself, and
+    /// properties are removed, as `tiled` rejects some property types and they are read here
+    /// instead. CSV tile data is checked for gids that aren't numbers, which `tiled` panics on,
+    /// and tile data stored as `<tile>` elements is converted to CSV. Images embedded in
+    /// tilesets are left without data, which `tiled` can't read.
+    pub fn tiled_document(&self, map: &Element) -> Result<Element, TiledError> {
+        let mut document = map.clone();
+        for element in document.children.iter_mut() {
+            if element.name != "tileset" {
+                continue;
+            }
+            let first_gid = element.attribute::<u32>("firstgid");
+            if let Some(external_tileset) =
+                first_gid.and_then(|first_gid| self.external_tilesets.get(&first_gid))
+            {
+                let mut tileset = external_tileset.clone();
+                tileset.set_attribute("firstgid", first_gid.unwrap().to_string());
+                *element = tileset;
+            }
+            remove_embedded_images(element);
+        }
+        remove_properties(&mut document);
+        rewrite_tile_data(&mut document)?;
+        Ok(document)
+    }
+}
+
+/// Removes the data of an image embedded in a tileset, leaving an image without a source. The
+/// data is decoded from the tileset's `TilesetExtras` instead.
+fn remove_embedded_images(tileset: &mut Element) {
+    for image in tileset
+        .children
+        .iter_mut()
+        .filter(|child| child.name == "image" && child.child("data").is_some())
+    {
+        image.children.retain(|child| child.name != "data");
+        if image.attribute::<String>("source").is_none() {
+            image.set_attribute("source", String::new());
+        }
+    }
+}
+
+/// Rewrites the CSV tile data of the layers in an element with one gid per value, trimmed of the
+/// whitespace `tiled` doesn't accept.
+///
+/// Tile data without an encoding lists each tile as a `<tile>` element, which `tiled` rejects, so
+/// it is rewritten as CSV. Tiled leaves out the `gid` of empty tiles.
+fn rewrite_tile_data(element: &mut Element) -> Result<(), TiledError> {
+    if element.name == "data"
+        && element.attribute::<String>("encoding").is_none()
+        && element.child("tile").is_some()
+    {
+        element.text = element
+            .children_named("tile")
+            .map(|tile| tile.attribute::<u32>("gid").unwrap_or(0).to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        element.children.retain(|child| child.name != "tile");
+        element.set_attribute("encoding", "csv".to_string());
+    }
+    if element.name == "data" && element.attribute::<String>("encoding").as_deref() == Some("csv") {
+        let gids = element
+            .text
+            .split(&[',', '\n'][..])
+            .map(str::trim)
+            .filter(|gid| !gid.is_empty())
+            .map(|gid| gid.parse::<u32>().map(|_| gid))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(TiledError::IntParse)?;
+        element.text = gids.join(",");
+    }
+    element.children.iter_mut().try_for_each(rewrite_tile_data)
+}
+
+/// Reads the `<text>` of a text object, with Tiled's defaults for missing attributes.
+fn parse_text(text: &Element) -> TiledObjectText {
+    let flag = |name| text.attribute::<u8>(name).unwrap_or(0) != 0;
+    TiledObjectText {
+        text: text.text.clone(),
+        font_family: text
+            .attribute("fontfamily")
+            .unwrap_or_else(|| "sans-serif".to_string()),
+        pixel_size: text.attribute("pixelsize").unwrap_or(16.0),
+        color: text
+            .attribute::<String>("color")
+            .and_then(|color| parse_color(&color))
+            .unwrap_or(Color::BLACK),
+        wrap: flag("wrap"),
+        bold: flag("bold"),
+        italic: flag("italic"),
+        halign: match text.attribute::<String>("halign").as_deref() {
+            Some("center") => TextHAlign::Center,
+            Some("right") => TextHAlign::Right,
+            Some("justify") => TextHAlign::Justify,
+            _ => TextHAlign::Left,
+        },
+        valign: match text.attribute::<String>("valign").as_deref() {
+            Some("center") => TextVAlign::Center,
+            Some("bottom") => TextVAlign::Bottom,
+            _ => TextVAlign::Top,
+        },
+    }
+}
+
+/// Reads a `<wangset>` defined in the file at `path`, in the format of Tiled 1.5 and later.
+fn parse_wang_set(wang_set: &Element, path: &Path) -> TiledWangSet {
+    // Tiled writes -1 for sets and colors without a tile, which doesn't parse as a tile id.
+    let tile = |element: &Element| element.attribute::<u32>("tile");
+    TiledWangSet {
+        name: wang_set.attribute("name").unwrap_or_default(),
+        set_type: match wang_set.attribute::<String>("type").as_deref() {
+            Some("edge") => WangSetType::Edge,
+            Some("mixed") => WangSetType::Mixed,
+            _ => WangSetType::Corner,
+        },
+        tile: tile(wang_set),
+        colors: wang_set
+            .children_named("wangcolor")
+            .map(|color| TiledWangColor {
+                name: color.attribute("name").unwrap_or_default(),
+                color: color
+                    .attribute::<String>("color")
+                    .and_then(|color| parse_color(&color))
+                    .unwrap_or(Color::WHITE),
+                tile: tile(color),
+                probability: color.attribute("probability").unwrap_or(1.0),
+                properties: parse_properties(color, path),
+            })
+            .collect(),
+        tiles: wang_set
+            .children_named("wangtile")
+            .filter_map(|wang_tile| {
+                let tile_id = wang_tile.attribute("tileid")?;
+                let mut wang_id = WangId::default();
+                let colors = wang_tile.attribute::<String>("wangid")?;
+                for (color, value) in wang_id.0.iter_mut().zip(colors.split(',')) {
+                    *color = value.trim().parse().ok()?;
+                }
+                Some((tile_id, wang_id))
+            })
+            .collect(),
+        properties: parse_properties(wang_set, path),
+    }
+}
+
+/// Reads the `class` of an element, which Tiled wrote as `type` before version 1.9.
+fn parse_class(element: &Element) -> Option<String> {
+    element
+        .attribute::<String>("class")
+        .filter(|class| !class.is_empty())
+        .or_else(|| element.attribute::<String>("type"))
+        .filter(|class| !class.is_empty())
+}
+
+/// Reads the `offsetx` and `offsety` attributes of a layer or group.
+fn parse_offset(element: &Element) -> Vec2 {
+    Vec2::new(
+        element.attribute("offsetx").unwrap_or(0.0),
+        element.attribute("offsety").unwrap_or(0.0),
+    )
+}
+
+fn remove_properties(element: &mut Element) {
+    element.children.retain(|child| child.name != "properties");
+    for child in element.children.iter_mut() {
+        remove_properties(child);
+    }
+}
+
+/// Reads the `<properties>` of an element defined in the file at `path`.
+fn parse_properties(element: &Element, path: &Path) -> TiledProperties {
+    let mut properties = TiledProperties::new();
+    for property in element
+        .children_named("properties")
+        .flat_map(|properties| properties.children_named("property"))
+    {
+        let name = match property.attribute::<String>("name") {
+            Some(name) => name,
+            None => continue,
+        };
+        // Multi-line strings are stored as the element's text instead of a value attribute.
+        let value = property
+            .attribute::<String>("value")
+            .unwrap_or_else(|| property.text.clone());
+        let value = match property.attribute::<String>("type").as_deref() {
+            Some("int") => value.parse().ok().map(PropertyValue::Int),
+            Some("float") => value.parse().ok().map(PropertyValue::Float),
+            Some("bool") => value.parse().ok().map(PropertyValue::Bool),
+            Some("color") => parse_color(&value).map(PropertyValue::Color),
+            Some("file") => Some(PropertyValue::File(resolve_path(path, &value))),
+            _ => Some(PropertyValue::String(value)),
+        };
+        if let Some(value) = value {
+            properties.insert(name, value);
+        }
+    }
+    properties
+}
+
+/// Resolves a path written in the file at `path`, which Tiled stores relative to that file's
+/// directory.
+///
+/// `.` and `..` are resolved too, so a file referenced from maps in different directories is
+/// loaded as the same asset.
+pub fn resolve_path(path: &Path, relative: &str) -> PathBuf {
+    let mut resolved = PathBuf::new();
+    for component in path.with_file_name(relative).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match resolved.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    resolved.pop();
+                }
+                Some(Component::RootDir) | Some(Component::Prefix(_)) => {}
+                _ => resolved.push(".."),
+            },
+            component => resolved.push(component),
+        }
+    }
+    resolved
+}
+
+/// Reads an element's `tintcolor` attribute.
+fn parse_tint(element: &Element) -> Option<Color> {
+    element
+        .attribute::<String>("tintcolor")
+        .and_then(|color| parse_color(&color))
+}
+
+fn parse_parallax(element: &Element) -> Vec2 {
+    Vec2::new(
+        element.attribute("parallaxx").unwrap_or(1.0),
+        element.attribute("parallaxy").unwrap_or(1.0),
+    )
+}
+
+/// Parses a Tiled color, which is written as `#rrggbb` or `#aarrggbb`.
+pub fn parse_color(value: &str) -> Option<Color> {
+    let hex = value.trim_start_matches('#');
+    match hex.len() {
+        6 => Color::hex(hex).ok(),
+        8 => Color::hex(format!("{}{}", &hex[2..], &hex[..2])).ok(),
+        _ => None,
+    }
+}
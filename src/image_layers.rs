@@ -0,0 +1,48 @@
+use crate::TiledProperties;
+use bevy::render::color::Color;
+use glam::Vec2;
+use std::path::PathBuf;
+
+/// An `<imagelayer>`, which places a single image on the map.
+#[derive(Debug, Clone)]
+pub struct TiledImageLayer {
+    /// The layer's id, unique across the map and kept when layers are renamed or moved. Maps
+    /// saved before Tiled 1.2 don't have layer ids.
+    pub id: Option<u32>,
+    pub name: String,
+    pub class: Option<String>,
+    /// The image's path, resolved relative to the map, or `None` if the layer has no image.
+    pub image: Option<PathBuf>,
+    /// The size of the image in pixels.
+    pub size: Vec2,
+    /// The position of the image's top left corner in pixels of the rendered map, with y pointing
+    /// down. Includes the offsets of the layer's groups.
+    pub offset: Vec2,
+    /// The color the image is multiplied with, including the tints of the layer's groups.
+    pub tint_color: Option<Color>,
+    /// Whether Tiled repeats the image horizontally to fill the view. The spawned sprite isn't
+    /// repeated, and draws the image once.
+    pub repeat_x: bool,
+    /// Whether Tiled repeats the image vertically to fill the view. The spawned sprite isn't
+    /// repeated, and draws the image once.
+    pub repeat_y: bool,
+    pub opacity: f32,
+    pub visible: bool,
+    /// The z the image is drawn at relative to the map's transform, between the tile layers
+    /// before and after it.
+    pub z: f32,
+    pub properties: TiledProperties,
+}
+
+impl TiledImageLayer {
+    /// Returns the color the image is drawn with, combining its tint and opacity.
+    pub fn color(&self) -> Color {
+        let tint_color = self.tint_color.unwrap_or(Color::WHITE);
+        Color::rgba(
+            tint_color.r,
+            tint_color.g,
+            tint_color.b,
+            tint_color.a * self.opacity,
+        )
+    }
+}
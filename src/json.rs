@@ -0,0 +1,372 @@
+use crate::{element::Element, TiledError};
+use serde_json::{Map as JsonObject, Value};
+
+/// Parses a JSON map, as Tiled exports to `.tmj` files, into the equivalent TMX document.
+///
+/// JSON maps are converted instead of read separately so that both formats go through the same
+/// parsing and load into identical maps.
+pub fn parse_map(bytes: &[u8]) -> Result<Element, TiledError> {
+    let map: Value = serde_json::from_slice(bytes).map_err(json_error)?;
+    let map = map
+        .as_object()
+        .ok_or_else(|| TiledError::Json("expected the map to be a JSON object".to_string()))?;
+
+    let mut element = element_with_attributes("map", map, &["type"]);
+    push_properties(&mut element, map);
+    if let Some(settings) = map.get("editorsettings").and_then(Value::as_object) {
+        let mut settings_element = element_with_attributes("editorsettings", settings, &[]);
+        for name in ["chunksize", "export"].iter() {
+            if let Some(child) = settings.get(*name).and_then(Value::as_object) {
+                settings_element
+                    .children
+                    .push(element_with_attributes(name, child, &[]));
+            }
+        }
+        element.children.push(settings_element);
+    }
+    for tileset in objects(map, "tilesets") {
+        element.children.push(tileset_element(tileset));
+    }
+    push_layers(&mut element, map);
+    Ok(element)
+}
+
+/// Parses a JSON tileset, as Tiled exports to `.tsj` files, into the equivalent TSX document.
+pub fn parse_tileset(bytes: &[u8]) -> Result<Element, TiledError> {
+    let tileset: Value = serde_json::from_slice(bytes).map_err(json_error)?;
+    let tileset = tileset
+        .as_object()
+        .ok_or_else(|| TiledError::Json("expected the tileset to be a JSON object".to_string()))?;
+    Ok(tileset_element(tileset))
+}
+
+/// Parses a JSON object template, as Tiled exports to `.tj` files, into the equivalent TX
+/// document.
+pub fn parse_template(bytes: &[u8]) -> Result<Element, TiledError> {
+    let template: Value = serde_json::from_slice(bytes).map_err(json_error)?;
+    let template = template
+        .as_object()
+        .ok_or_else(|| TiledError::Json("expected the template to be a JSON object".to_string()))?;
+
+    let mut element = Element {
+        name: "template".to_string(),
+        ..Default::default()
+    };
+    if let Some(tileset) = template.get("tileset").and_then(Value::as_object) {
+        element
+            .children
+            .push(element_with_attributes("tileset", tileset, &[]));
+    }
+    if let Some(object) = template.get("object").and_then(Value::as_object) {
+        element.children.push(object_element(object));
+    }
+    Ok(element)
+}
+
+fn json_error(error: serde_json::Error) -> TiledError {
+    TiledError::Json(error.to_string())
+}
+
+fn tileset_element(tileset: &JsonObject<String, Value>) -> Element {
+    let mut element = element_with_attributes(
+        "tileset",
+        tileset,
+        &[
+            "type",
+            "image",
+            "imagewidth",
+            "imageheight",
+            "transparentcolor",
+        ],
+    );
+    for name in ["tileoffset", "grid", "transformations"].iter() {
+        if let Some(child) = tileset.get(*name).and_then(Value::as_object) {
+            element
+                .children
+                .push(element_with_attributes(name, child, &[]));
+        }
+    }
+    push_properties(&mut element, tileset);
+    push_image(&mut element, tileset);
+    if tileset.contains_key("wangsets") {
+        let wang_sets = objects(tileset, "wangsets").map(wang_set_element).collect();
+        element.children.push(Element {
+            name: "wangsets".to_string(),
+            children: wang_sets,
+            ..Default::default()
+        });
+    }
+
+    for tile in objects(tileset, "tiles") {
+        let mut tile_element = element_with_attributes("tile", tile, &IMAGE_KEYS);
+        push_properties(&mut tile_element, tile);
+        push_image(&mut tile_element, tile);
+        if let Some(object_group) = tile.get("objectgroup").and_then(Value::as_object) {
+            tile_element.children.push(layer_element(object_group));
+        }
+        let frames = objects(tile, "animation")
+            .map(|frame| element_with_attributes("frame", frame, &[]))
+            .collect::<Vec<_>>();
+        if !frames.is_empty() {
+            tile_element.children.push(Element {
+                name: "animation".to_string(),
+                children: frames,
+                ..Default::default()
+            });
+        }
+        element.children.push(tile_element);
+    }
+    element
+}
+
+fn wang_set_element(wang_set: &JsonObject<String, Value>) -> Element {
+    let mut element = element_with_attributes("wangset", wang_set, &[]);
+    push_properties(&mut element, wang_set);
+    for color in objects(wang_set, "colors") {
+        let mut color_element = element_with_attributes("wangcolor", color, &[]);
+        push_properties(&mut color_element, color);
+        element.children.push(color_element);
+    }
+    for wang_tile in objects(wang_set, "wangtiles") {
+        let mut wang_tile_element = element_with_attributes("wangtile", wang_tile, &[]);
+        if let Some(colors) = wang_tile.get("wangid").and_then(Value::as_array) {
+            let colors = colors.iter().map(scalar_attribute).collect::<Vec<_>>();
+            wang_tile_element.set_attribute("wangid", colors.join(","));
+        }
+        element.children.push(wang_tile_element);
+    }
+    element
+}
+
+/// Converts the `layers` of the map or a group.
+fn push_layers(element: &mut Element, parent: &JsonObject<String, Value>) {
+    for layer in objects(parent, "layers") {
+        element.children.push(layer_element(layer));
+    }
+}
+
+fn layer_element(layer: &JsonObject<String, Value>) -> Element {
+    let name = match layer.get("type").and_then(Value::as_str) {
+        Some("tilelayer") => "layer",
+        Some(name) => name,
+        // The collision shapes of tiles are object groups without a type.
+        None => "objectgroup",
+    };
+    let mut element = element_with_attributes(
+        name,
+        layer,
+        &[
+            "type",
+            "x",
+            "y",
+            "data",
+            "encoding",
+            "compression",
+            "image",
+            "imagewidth",
+            "imageheight",
+            "transparentcolor",
+        ],
+    );
+    push_properties(&mut element, layer);
+
+    match name {
+        "layer" => {
+            let width = layer.get("width").and_then(Value::as_u64).unwrap_or(0);
+            let mut data = Element {
+                name: "data".to_string(),
+                ..Default::default()
+            };
+            for name in ["encoding", "compression"].iter() {
+                if let Some(value) = layer.get(*name).and_then(Value::as_str) {
+                    if !value.is_empty() {
+                        data.set_attribute(name, value.to_string());
+                    }
+                }
+            }
+            if layer.get("encoding").is_none() {
+                data.set_attribute("encoding", "csv".to_string());
+            }
+            match layer.get("data") {
+                Some(tiles) => data.text = tile_data(tiles, width),
+                None => {
+                    for chunk in objects(layer, "chunks") {
+                        let width = chunk.get("width").and_then(Value::as_u64).unwrap_or(0);
+                        let mut chunk_element = element_with_attributes("chunk", chunk, &[]);
+                        if let Some(tiles) = chunk.get("data") {
+                            chunk_element.text = tile_data(tiles, width);
+                        }
+                        data.children.push(chunk_element);
+                    }
+                }
+            }
+            element.children.push(data);
+        }
+        "objectgroup" => {
+            for object in objects(layer, "objects") {
+                element.children.push(object_element(object));
+            }
+        }
+        "imagelayer" => push_image(&mut element, layer),
+        "group" => push_layers(&mut element, layer),
+        _ => {}
+    }
+    element
+}
+
+fn object_element(object: &JsonObject<String, Value>) -> Element {
+    let mut element = element_with_attributes("object", object, &["ellipse", "point"]);
+    push_properties(&mut element, object);
+    for name in ["ellipse", "point"].iter() {
+        if object.get(*name).and_then(Value::as_bool) == Some(true) {
+            element.children.push(Element {
+                name: name.to_string(),
+                ..Default::default()
+            });
+        }
+    }
+    for name in ["polygon", "polyline"].iter() {
+        if object.contains_key(*name) {
+            let points = objects(object, name)
+                .map(|point| {
+                    format!(
+                        "{},{}",
+                        scalar_attribute(point.get("x").unwrap_or(&Value::Null)),
+                        scalar_attribute(point.get("y").unwrap_or(&Value::Null)),
+                    )
+                })
+                .collect::<Vec<_>>();
+            let mut points_element = Element {
+                name: name.to_string(),
+                ..Default::default()
+            };
+            points_element.set_attribute("points", points.join(" "));
+            element.children.push(points_element);
+        }
+    }
+    if let Some(text) = object.get("text").and_then(Value::as_object) {
+        let mut text_element = element_with_attributes("text", text, &["text"]);
+        text_element.text = text
+            .get("text")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        element.children.push(text_element);
+    }
+    element
+}
+
+/// The keys JSON uses for an element's image, which TMX stores in an `<image>` child.
+const IMAGE_KEYS: [&str; 4] = ["image", "imagewidth", "imageheight", "transparentcolor"];
+
+fn push_image(element: &mut Element, object: &JsonObject<String, Value>) {
+    let source = match object.get("image").and_then(Value::as_str) {
+        Some(source) if !source.is_empty() => source,
+        _ => return,
+    };
+    let mut image = Element {
+        name: "image".to_string(),
+        ..Default::default()
+    };
+    image.set_attribute("source", source.to_string());
+    for (key, name) in [
+        ("imagewidth", "width"),
+        ("imageheight", "height"),
+        ("transparentcolor", "trans"),
+    ]
+    .iter()
+    {
+        if let Some(value) = object.get(*key) {
+            image.set_attribute(name, scalar_attribute(value));
+        }
+    }
+    element.children.push(image);
+}
+
+fn push_properties(element: &mut Element, object: &JsonObject<String, Value>) {
+    let properties = objects(object, "properties")
+        .map(|property| {
+            let mut property_element = element_with_attributes("property", property, &["value"]);
+            let value = match property.get("value") {
+                Some(Value::Bool(value)) => value.to_string(),
+                Some(value) => scalar_attribute(value),
+                None => String::new(),
+            };
+            property_element.set_attribute("value", value);
+            property_element
+        })
+        .collect::<Vec<_>>();
+    if !properties.is_empty() {
+        element.children.push(Element {
+            name: "properties".to_string(),
+            children: properties,
+            ..Default::default()
+        });
+    }
+}
+
+/// Creates an element with the scalar values of a JSON object as attributes, except for the
+/// listed keys.
+fn element_with_attributes(
+    name: &str,
+    object: &JsonObject<String, Value>,
+    skip: &[&str],
+) -> Element {
+    let mut element = Element {
+        name: name.to_string(),
+        ..Default::default()
+    };
+    for (key, value) in object.iter() {
+        if skip.contains(&key.as_str()) {
+            continue;
+        }
+        match value {
+            Value::Bool(_) | Value::Number(_) | Value::String(_) => {
+                element.set_attribute(key, scalar_attribute(value))
+            }
+            _ => {}
+        }
+    }
+    element
+}
+
+/// Writes a JSON value the way TMX writes attributes, which stores booleans as 1 and 0.
+fn scalar_attribute(value: &Value) -> String {
+    match value {
+        Value::Bool(value) => (*value as u8).to_string(),
+        Value::String(value) => value.clone(),
+        Value::Null => String::new(),
+        value => value.to_string(),
+    }
+}
+
+/// Converts layer data to TMX text. Gid arrays become CSV rows, base64 strings are kept as is.
+fn tile_data(tiles: &Value, width: u64) -> String {
+    match tiles {
+        Value::Array(gids) => gids
+            .chunks(width.max(1) as usize)
+            .map(|row| {
+                row.iter()
+                    .map(scalar_attribute)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .collect::<Vec<_>>()
+            .join(",\n"),
+        Value::String(data) => data.clone(),
+        _ => String::new(),
+    }
+}
+
+/// Iterates the objects in an array of a JSON object, skipping anything else.
+fn objects<'a>(
+    object: &'a JsonObject<String, Value>,
+    key: &str,
+) -> impl Iterator<Item = &'a JsonObject<String, Value>> {
+    object
+        .get(key)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_object)
+}
@@ -1,22 +1,96 @@
-use bevy::{prelude::*, render::render_graph::RenderGraph};
+// bevy's `Bundle` derive forgets each field after moving it into the entity.
+#![allow(clippy::forget_non_drop)]
 
+use bevy::{
+    prelude::*,
+    render::{render_graph::RenderGraph, texture::TextureFormat},
+};
+
+mod animation;
+pub use animation::*;
+mod builder;
+pub use builder::*;
+mod colliders;
+pub use colliders::*;
+mod culling;
+pub use culling::*;
+mod element;
+mod error;
+pub use error::*;
+mod extras;
+mod image_layers;
+pub use image_layers::*;
+mod json;
 mod loader;
 mod map;
 pub use map::*;
+mod objects;
+pub use objects::*;
+mod parallax;
+pub use parallax::*;
 mod pipeline;
 pub use pipeline::*;
+mod properties;
+pub use properties::*;
+mod templates;
+mod tile_grid;
+pub use tile_grid::*;
 mod tile_map;
 pub use tile_map::*;
+mod visibility;
+pub use visibility::*;
+mod wang;
+pub use wang::*;
 
 /// Adds support for GLTF file loading to Apps
 #[derive(Default)]
-pub struct TiledMapPlugin;
+pub struct TiledMapPlugin {
+    /// Draws tilesets and image layers whose image file is missing with a magenta placeholder,
+    /// logging a warning for each, instead of leaving them blank.
+    pub placeholder_on_missing_image: bool,
+    /// Loads `.json` files as maps too, besides `.tmx` and `.tmj` ones. This claims every `.json`
+    /// asset, so only enable it if the app loads no other JSON assets.
+    pub load_json_maps: bool,
+}
 
 impl Plugin for TiledMapPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.add_asset::<map::Map>()
             .add_asset_loader::<map::Map, loader::TiledMapLoader>()
-            .add_system(process_loaded_tile_maps.system());
+            .add_event::<DespawnTiledMap>()
+            .add_event::<MapSpawned>()
+            .add_event::<TileChanged>()
+            .init_resource::<TiledLayerRefreshes>()
+            .add_system(process_loaded_tile_maps.system())
+            .add_system(add_embedded_images.system())
+            .add_system(draw_missing_images.system())
+            .add_system(respawn_changed_tiles.system())
+            .add_system(respawn_refreshed_layers.system())
+            .add_system(animate_tiles.system())
+            .add_system(animate_tile_sprites.system())
+            .add_system(apply_layer_parallax.system())
+            .add_system(cull_chunks.system())
+            .add_system(apply_layer_visibility.system())
+            .add_system(despawn_tiled_maps.system());
+
+        if self.load_json_maps {
+            app.add_asset_loader::<map::Map, loader::TiledJsonMapLoader>();
+        }
+
+        let placeholder = if self.placeholder_on_missing_image {
+            let mut textures = app.resources().get_mut::<Assets<Texture>>().unwrap();
+            Some(textures.add(Texture::new(
+                Vec2::new(1.0, 1.0),
+                vec![255, 0, 255, 255],
+                TextureFormat::Rgba8UnormSrgb,
+            )))
+        } else {
+            None
+        };
+        app.add_resource(TiledTextures {
+            placeholder,
+            ..Default::default()
+        });
 
         let resources = app.resources();
         let mut render_graph = resources.get_mut::<RenderGraph>().unwrap();
@@ -1,281 +1,679 @@
 use crate::{
-    map::{Chunk, Map},
-    Layer, Tile, TilesetLayer,
+    element::Element,
+    extras::{resolve_path, EmbeddedImage, MapExtras},
+    json,
+    map::{Chunk, Map, TiledEmbeddedImage},
+    templates::apply_templates,
+    GridOrientation, HexLayout, Layer, Tile, TiledError, TiledGrid, TiledImageLayer, TiledObject,
+    TiledObjectLayer, TiledObjectShape, TiledTileAnimation, TilesetLayer,
 };
-use anyhow::Result;
+use anyhow::Context;
 use bevy::{
-    asset::AssetLoader,
-    prelude::Mesh,
-    render::{mesh::VertexAttribute, pipeline::PrimitiveTopology},
+    asset::{AssetLoader, Handle},
+    render::texture::{ImageTextureLoader, Texture},
 };
 use glam::{Vec2, Vec4};
+use log::warn;
 
-use std::{io::BufReader, path::Path};
+use std::{collections::HashMap, io::BufReader, ops::Range, path::Path};
 
+/// Loads `.tmx` and `.tmj` maps into `Map` assets.
+///
+/// The asset server runs loaders on its own background threads, so parsing a map, its tilesets
+/// and its tile data doesn't block the app's systems.
 #[derive(Default)]
 pub struct TiledMapLoader;
 
 impl TiledMapLoader {
     fn remove_tile_flags(tile: u32) -> u32 {
-        let tile = tile & !ALL_FLIP_FLAGS;
-        tile
+        tile & !ALL_FLIP_FLAGS
     }
 }
 
-const FLIPPED_HORIZONTALLY_FLAG: u32 = 0x80000000;
-const FLIPPED_VERTICALLY_FLAG: u32 = 0x40000000;
-const FLIPPED_DIAGONALLY_FLAG: u32 = 0x20000000;
-const ALL_FLIP_FLAGS: u32 =
-    FLIPPED_HORIZONTALLY_FLAG | FLIPPED_VERTICALLY_FLAG | FLIPPED_DIAGONALLY_FLAG;
+/// An image tiles are drawn from, either a tileset's sheet or a single tile's image in an image
+/// collection tileset.
+pub(crate) struct TilesetTexture<'a> {
+    tileset: &'a tiled::Tileset,
+    image: &'a tiled::Image,
+    /// The key of the image in `Map::tileset_image_paths`.
+    key: u32,
+    /// The gids drawn from this image.
+    gids: Range<u32>,
+    tile_size: Vec2,
+    is_collection: bool,
+}
 
-impl AssetLoader<Map> for TiledMapLoader {
-    fn from_bytes(&self, asset_path: &Path, bytes: Vec<u8>) -> Result<Map> {
-        let map = tiled::parse_with_path(BufReader::new(bytes.as_slice()), asset_path).unwrap();
+/// Returns the images the tiles of each tileset are drawn from, in the order of `tilesets`.
+pub(crate) fn tileset_textures(tilesets: &[tiled::Tileset]) -> Vec<TilesetTexture<'_>> {
+    let mut textures = Vec::new();
+    for tileset in tilesets.iter() {
+        if let Some(image) = tileset.images.first() {
+            let tile_count = tileset
+                .tilecount
+                .unwrap_or_else(|| tileset_columns(tileset, image) * tileset_rows(tileset, image));
+            textures.push(TilesetTexture {
+                tileset,
+                image,
+                key: tileset.first_gid,
+                gids: tileset.first_gid..tileset.first_gid + tile_count,
+                tile_size: Vec2::new(tileset.tile_width as f32, tileset.tile_height as f32),
+                is_collection: false,
+            });
+            continue;
+        }
 
-        let mut layers = Vec::new();
+        // Image collection tilesets give each tile its own image instead of a sheet.
+        for tile in tileset.tiles.iter() {
+            if let Some(image) = tile.images.first() {
+                let gid = tileset.first_gid + tile.id;
+                textures.push(TilesetTexture {
+                    tileset,
+                    image,
+                    key: gid,
+                    gids: gid..gid + 1,
+                    tile_size: Vec2::new(image.width as f32, image.height as f32),
+                    is_collection: true,
+                });
+            }
+        }
+    }
+    textures
+}
+
+/// Decodes the image embedded in a tileset with the decoder for its `format`.
+fn decode_embedded_image(
+    tileset: &tiled::Tileset,
+    image: &EmbeddedImage,
+) -> Result<Texture, TiledError> {
+    let invalid_image = |reason: String| TiledError::InvalidImage {
+        tileset: tileset.name.clone(),
+        reason,
+    };
+    // bevy only decodes png images.
+    match image.format.as_deref() {
+        Some(format) if format.eq_ignore_ascii_case("png") => {}
+        Some(format) => {
+            return Err(TiledError::Unsupported(format!(
+                "tileset {:?} embeds a {} image, but only png images are supported",
+                tileset.name, format
+            )))
+        }
+        None => return Err(invalid_image("it has no format".to_string())),
+    }
+    let bytes =
+        base64::decode(image.data.trim()).map_err(|error| invalid_image(error.to_string()))?;
+    ImageTextureLoader
+        .from_bytes(Path::new("embedded.png"), bytes)
+        .map_err(|error| invalid_image(error.to_string()))
+}
 
-        let target_chunk_x = 32;
-        let target_chunk_y = 32;
+/// Returns how many tiles fit next to each other in a tileset's image.
+pub(crate) fn tileset_columns(tileset: &tiled::Tileset, image: &tiled::Image) -> u32 {
+    let usable_width = (image.width as u32).saturating_sub(tileset.margin * 2) + tileset.spacing;
+    usable_width / (tileset.tile_width + tileset.spacing)
+}
 
-        let chunk_size_x = (map.width as f32 / target_chunk_x as f32).ceil().max(1.0) as usize;
-        let chunk_size_y = (map.height as f32 / target_chunk_y as f32).ceil().max(1.0) as usize;
-        let tile_size = Vec2::new(map.tile_width as f32, map.tile_height as f32);
+/// Returns how many tiles fit on top of each other in a tileset's image.
+pub(crate) fn tileset_rows(tileset: &tiled::Tileset, image: &tiled::Image) -> u32 {
+    let usable_height = (image.height as u32).saturating_sub(tileset.margin * 2) + tileset.spacing;
+    usable_height / (tileset.tile_height + tileset.spacing)
+}
 
-        for layer in map.layers.iter() {
-            if !layer.visible {
-                continue;
+/// The number of tiles along each side of the chunks tile layers are laid out in.
+pub(crate) const CHUNK_TILES: usize = 32;
+
+/// Lays the tiles of a tile layer out into chunks for each image they are drawn from.
+///
+/// `offset` is the layer's offset in world units, with y pointing up.
+pub(crate) fn build_tileset_layers(
+    map: &tiled::Map,
+    layer: &tiled::Layer,
+    textures: &[TilesetTexture],
+    tile_offsets: &HashMap<u32, Vec2>,
+    offset: Vec2,
+    hex_layout: HexLayout,
+) -> Result<Vec<TilesetLayer>, TiledError> {
+    let chunk_size_x = (map.width as f32 / CHUNK_TILES as f32).ceil().max(1.0) as usize;
+    let chunk_size_y = (map.height as f32 / CHUNK_TILES as f32).ceil().max(1.0) as usize;
+
+    let mut tileset_layers = textures
+        .iter()
+        .map(|texture| TilesetLayer {
+            tile_size: texture.tile_size,
+            chunks: Vec::new(),
+            tileset_guid: texture.key,
+        })
+        .collect::<Vec<_>>();
+    for chunk_x in 0..chunk_size_x {
+        for tileset_layer in tileset_layers.iter_mut() {
+            tileset_layer.chunks.push(Vec::new());
+        }
+        for chunk_y in 0..chunk_size_y {
+            let chunks = build_tileset_chunks(
+                map,
+                layer,
+                textures,
+                tile_offsets,
+                offset,
+                hex_layout,
+                (chunk_x, chunk_y),
+            )?;
+            for (tileset_layer, chunk) in tileset_layers.iter_mut().zip(chunks) {
+                tileset_layer.chunks[chunk_x].push(chunk);
             }
-            let mut tileset_layers = Vec::new();
-
-            for tileset in map.tilesets.iter() {
-                let tile_width = tileset.tile_width as f32;
-                let tile_height = tileset.tile_height as f32;
-                let image = tileset.images.first().unwrap();
-                let texture_width = image.width as f32;
-                let texture_height = image.height as f32;
-                let columns = (texture_width / tile_width).floor();
-
-                let mut chunks = Vec::new();
-                // 32 x 32 tile chunk sizes
-                for chunk_x in 0..chunk_size_x {
-                    let mut chunks_y = Vec::new();
-                    for chunk_y in 0..chunk_size_y {
-                        let mut tiles = Vec::new();
-
-                        for tile_x in 0..target_chunk_x {
-                            let mut tiles_y = Vec::new();
-                            for tile_y in 0..target_chunk_y {
-                                let lookup_x = (chunk_x * target_chunk_x) + tile_x;
-                                let lookup_y = (chunk_y * target_chunk_y) + tile_y;
-
-                                // Get chunk tile.
-                                let chunk_tile = if lookup_x < map.width as usize
-                                    && lookup_y < map.height as usize
-                                {
-                                    // New Tiled crate code:
-                                    let map_tile = match &layer.tiles {
-                                        tiled::LayerData::Finite(tiles) => {
-                                            &tiles[lookup_y][lookup_x]
-                                        }
-                                        _ => panic!("Infinte maps not supported"),
-                                    };
-
-                                    let tile = map_tile.gid;
-                                    if tile < tileset.first_gid
-                                        || tile >= tileset.first_gid + tileset.tilecount.unwrap()
-                                    {
-                                        continue;
-                                    }
-
-                                    let tile = (Self::remove_tile_flags(tile) as f32)
-                                        - tileset.first_gid as f32;
-
-                                    // This calculation is much simpler we only care about getting the remainder
-                                    // and multiplying that by the tile width.
-                                    let sprite_sheet_x: f32 = (tile % columns * tile_width).floor();
-
-                                    // Calculation here is (tile / columns).round_down * tile_height
-                                    // Example: tile 30 / 28 columns = 1.0714 rounded down to 1 * 16 tile_height = 16 Y
-                                    // which is the 2nd row in the sprite sheet.
-                                    // Example2: tile 10 / 28 columns = 0.3571 rounded down to 0 * 16 tile_height = 0 Y
-                                    // which is the 1st row in the sprite sheet.
-                                    let sprite_sheet_y: f32 =
-                                        (tile / columns).floor() * tile_height;
-
-                                    // Calculate positions
-                                    let (start_x, end_x, start_y, end_y) = match map.orientation {
-                                        tiled::Orientation::Orthogonal => {
-                                            let center = Map::project_ortho(
-                                                Vec2::new(lookup_x as f32, lookup_y as f32),
-                                                tile_width,
-                                                tile_height,
-                                            );
-
-                                            let start = Vec2::new(
-                                                center.x() - tile_width / 2.0,
-                                                center.y() - tile_height / 2.0,
-                                            );
-
-                                            let end = Vec2::new(
-                                                center.x() + tile_width / 2.0,
-                                                center.y() + tile_height / 2.0,
-                                            );
-
-                                            (start.x(), end.x(), start.y(), end.y())
-                                        }
-                                        tiled::Orientation::Isometric => {
-                                            let center = Map::project_iso(
-                                                Vec2::new(lookup_x as f32, lookup_y as f32),
-                                                tile_width,
-                                                tile_height,
-                                            );
-
-                                            let start = Vec2::new(
-                                                center.x() - tile_width / 2.0,
-                                                center.y() - tile_height / 2.0,
-                                            );
-
-                                            let end = Vec2::new(
-                                                center.x() + tile_width / 2.0,
-                                                center.y() + tile_height / 2.0,
-                                            );
-
-                                            (start.x(), end.x(), start.y(), end.y())
-                                        }
-                                        _ => {
-                                            panic!("Unsupported orientation {:?}", map.orientation)
-                                        }
-                                    };
-
-                                    // Calculate UV:
-                                    let mut start_u: f32 = sprite_sheet_x / texture_width;
-                                    let mut end_u: f32 =
-                                        (sprite_sheet_x + tile_width) / texture_width;
-                                    let mut start_v: f32 = sprite_sheet_y / texture_height;
-                                    let mut end_v: f32 =
-                                        (sprite_sheet_y + tile_height) / texture_height;
-
-                                    if map_tile.flip_h {
-                                        let temp_startu = start_u;
-                                        start_u = end_u;
-                                        end_u = temp_startu;
-                                    }
-                                    if map_tile.flip_v {
-                                        let temp_startv = start_v;
-                                        start_v = end_v;
-                                        end_v = temp_startv;
-                                    }
-
-                                    Tile {
-                                        tile_id: map_tile.gid,
-                                        pos: Vec2::new(tile_x as f32, tile_y as f32),
-                                        vertex: Vec4::new(start_x, start_y, end_x, end_y),
-                                        uv: Vec4::new(start_u, start_v, end_u, end_v),
-                                    }
-                                } else {
-                                    // Empty tile
-                                    Tile {
-                                        tile_id: 0,
-                                        pos: Vec2::new(tile_x as f32, tile_y as f32),
-                                        vertex: Vec4::new(0.0, 0.0, 0.0, 0.0),
-                                        uv: Vec4::new(0.0, 0.0, 0.0, 0.0),
-                                    }
-                                };
-
-                                tiles_y.push(chunk_tile);
-                            }
-                            tiles.push(tiles_y);
-                        }
+        }
+    }
+    Ok(tileset_layers)
+}
 
-                        let chunk = Chunk {
-                            position: Vec2::new(chunk_x as f32, chunk_y as f32),
-                            tiles,
-                        };
-                        chunks_y.push(chunk);
+/// Lays out the tiles of one chunk of a tile layer for each image they are drawn from, in the
+/// order of `textures`. `chunk` is the chunk's column and row in the layer.
+pub(crate) fn build_tileset_chunks(
+    map: &tiled::Map,
+    layer: &tiled::Layer,
+    textures: &[TilesetTexture],
+    tile_offsets: &HashMap<u32, Vec2>,
+    offset: Vec2,
+    hex_layout: HexLayout,
+    (chunk_x, chunk_y): (usize, usize),
+) -> Result<Vec<Chunk>, TiledError> {
+    let tile_size = Vec2::new(map.tile_width as f32, map.tile_height as f32);
+    let mut chunks = Vec::new();
+    for texture in textures.iter() {
+        let tileset = texture.tileset;
+        let tile_offset = tile_offsets
+            .get(&tileset.first_gid)
+            .copied()
+            .unwrap_or_default();
+        let tile_offset = offset + Vec2::new(tile_offset.x(), -tile_offset.y());
+
+        let mut tiles = Vec::new();
+
+        for tile_x in 0..CHUNK_TILES {
+            let mut tiles_y = Vec::new();
+            for tile_y in 0..CHUNK_TILES {
+                let lookup_x = (chunk_x * CHUNK_TILES) + tile_x;
+                let lookup_y = (chunk_y * CHUNK_TILES) + tile_y;
+
+                // Get chunk tile.
+                let chunk_tile = if lookup_x < map.width as usize && lookup_y < map.height as usize
+                {
+                    // New Tiled crate code:
+                    let map_tile = match &layer.tiles {
+                        tiled::LayerData::Finite(tiles) => &tiles[lookup_y][lookup_x],
+                        _ => return Err(infinite_map_error()),
+                    };
+
+                    let tile = map_tile.gid;
+                    if !texture.gids.contains(&tile) {
+                        continue;
                     }
-                    chunks.push(chunks_y);
-                }
 
-                let tileset_layer = TilesetLayer {
-                    tile_size: Vec2::new(tile_width, tile_height),
-                    chunks,
-                    tileset_guid: tileset.first_gid,
+                    let tile = TiledMapLoader::remove_tile_flags(tile) - tileset.first_gid;
+
+                    // Calculate positions. Tiles are placed on the map's grid and, like in
+                    // Tiled, anchored to the bottom left of their cell so that tiles larger
+                    // than the grid extend up and to the right.
+                    let grid_position = Vec2::new(lookup_x as f32, lookup_y as f32);
+                    let center = match map.orientation {
+                        tiled::Orientation::Orthogonal => {
+                            Map::project_ortho(grid_position, tile_size.x(), tile_size.y())
+                        }
+                        tiled::Orientation::Isometric => {
+                            Map::project_iso(grid_position, tile_size.x(), tile_size.y())
+                        }
+                        tiled::Orientation::Hexagonal | tiled::Orientation::Staggered => {
+                            Map::project_hex(
+                                grid_position,
+                                tile_size.x(),
+                                tile_size.y(),
+                                hex_layout,
+                            )
+                        }
+                    };
+                    let start = center - tile_size / 2.0 + tile_offset;
+                    let end = start + texture.tile_size;
+                    let (start_x, end_x, start_y, end_y) = (start.x(), end.x(), start.y(), end.y());
+
+                    Tile {
+                        tile_id: map_tile.gid,
+                        pos: Vec2::new(tile_x as f32, tile_y as f32),
+                        vertex: Vec4::new(start_x, start_y, end_x, end_y),
+                        uv: if texture.is_collection {
+                            Vec4::new(0.0, 0.0, 1.0, 1.0)
+                        } else {
+                            tile_uv(tileset, texture.image, tile)
+                        },
+                        flip_h: map_tile.flip_h,
+                        flip_v: map_tile.flip_v,
+                        flip_d: map_tile.flip_d,
+                    }
+                } else {
+                    // Empty tile
+                    Tile {
+                        tile_id: 0,
+                        pos: Vec2::new(tile_x as f32, tile_y as f32),
+                        vertex: Vec4::new(0.0, 0.0, 0.0, 0.0),
+                        uv: Vec4::new(0.0, 0.0, 0.0, 0.0),
+                        flip_h: false,
+                        flip_v: false,
+                        flip_d: false,
+                    }
                 };
-                tileset_layers.push(tileset_layer);
+
+                tiles_y.push(chunk_tile);
             }
+            tiles.push(tiles_y);
+        }
 
-            let layer = Layer { tileset_layers };
-            layers.push(layer);
+        chunks.push(Chunk {
+            position: Vec2::new(chunk_x as f32, chunk_y as f32),
+            tiles,
+        });
+    }
+    Ok(chunks)
+}
+
+/// Returns the rect of a tile in its tileset's image as `(start_u, start_v, end_u, end_v)`.
+///
+/// Tiles are laid out row by row, starting `margin` pixels from the image's edges and
+/// `spacing` pixels apart.
+fn tile_uv(tileset: &tiled::Tileset, image: &tiled::Image, tile: u32) -> Vec4 {
+    let columns = tileset_columns(tileset, image).max(1);
+    let tile_width = tileset.tile_width as f32;
+    let tile_height = tileset.tile_height as f32;
+    let texture_width = image.width as f32;
+    let texture_height = image.height as f32;
+
+    // The remainder is the tile's column and the quotient its row.
+    // Example: tile 30 with 28 columns is in column 2 of row 1, the 2nd row in the sprite sheet.
+    let column = (tile % columns) as f32;
+    let row = (tile / columns) as f32;
+    let sprite_sheet_x = tileset.margin as f32 + column * (tile_width + tileset.spacing as f32);
+    let sprite_sheet_y = tileset.margin as f32 + row * (tile_height + tileset.spacing as f32);
+
+    // Calculate UV:
+    let start_u: f32 = sprite_sheet_x / texture_width;
+    let end_u: f32 = (sprite_sheet_x + tile_width) / texture_width;
+    let start_v: f32 = sprite_sheet_y / texture_height;
+    let end_v: f32 = (sprite_sheet_y + tile_height) / texture_height;
+
+    Vec4::new(start_u, start_v, end_u, end_v)
+}
+
+pub(crate) const FLIPPED_HORIZONTALLY_FLAG: u32 = 0x80000000;
+pub(crate) const FLIPPED_VERTICALLY_FLAG: u32 = 0x40000000;
+pub(crate) const FLIPPED_DIAGONALLY_FLAG: u32 = 0x20000000;
+pub(crate) const ALL_FLIP_FLAGS: u32 =
+    FLIPPED_HORIZONTALLY_FLAG | FLIPPED_VERTICALLY_FLAG | FLIPPED_DIAGONALLY_FLAG;
+
+fn infinite_map_error() -> TiledError {
+    TiledError::Unsupported("infinite maps are not supported".to_string())
+}
+
+impl TiledMapLoader {
+    /// Parses the map at `asset_path`, as `Map::from_bytes` does.
+    pub(crate) fn load_map(asset_path: &Path, bytes: Vec<u8>) -> Result<Map, TiledError> {
+        let bytes = String::from_utf8(bytes).map_err(TiledError::Utf8)?;
+        let document = match asset_path
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some("tmj") | Some("json") => json::parse_map(bytes.as_bytes()),
+            _ => Element::parse(bytes.as_bytes()),
+        }?;
+        // The map is parsed with its templates applied, but keeps its document as it was so
+        // that saving it still references them.
+        let mut resolved = document.clone();
+        apply_templates(&mut resolved, asset_path)?;
+        let extras = MapExtras::parse(&resolved, asset_path)?;
+        let tiled_bytes = extras.tiled_document(&resolved)?.to_bytes()?;
+        let mut map = tiled::parse_with_path(BufReader::new(tiled_bytes.as_slice()), asset_path)?;
+        // Tilesets may be declared in any order, but gid lookups expect ascending first gids.
+        map.tilesets.sort_by_key(|tileset| tileset.first_gid);
+
+        if map.infinite {
+            return Err(infinite_map_error());
         }
 
-        let mut meshes = Vec::new();
-        for (layer_id, layer) in layers.iter().enumerate() {
-            for tileset_layer in layer.tileset_layers.iter() {
-                for x in 0..tileset_layer.chunks.len() {
-                    let chunk_x = &tileset_layer.chunks[x];
-                    for y in 0..chunk_x.len() {
-                        let chunk = &chunk_x[y];
+        // Rows are read from the lines of CSV data and the width of base64 data, so a hand edited
+        // layer may not line up with the map. Lay the tiles out row by row and check their count.
+        for layer in map.layers.iter_mut() {
+            if let tiled::LayerData::Finite(rows) = &mut layer.tiles {
+                let tiles = rows.drain(..).flatten().collect::<Vec<_>>();
+                let expected = (map.width * map.height) as usize;
+                if tiles.len() != expected {
+                    return Err(TiledError::LayerSizeMismatch {
+                        layer: layer.name.clone(),
+                        expected,
+                        found: tiles.len(),
+                    });
+                }
+                rows.extend(
+                    tiles
+                        .chunks(map.width.max(1) as usize)
+                        .map(|row| row.to_vec()),
+                );
+            }
+        }
 
-                        let mut positions = Vec::new();
-                        let mut uvs = Vec::new();
-                        let mut indices = Vec::new();
+        // Image sources are relative to the file their tileset was defined in.
+        let mut tileset_image_paths = HashMap::new();
+        let mut tileset_image_sizes = HashMap::new();
+        let mut tileset_embedded_images = HashMap::new();
+        let mut tileset_atlases = HashMap::new();
+        let textures = tileset_textures(&map.tilesets);
+        if let Some(tileset) = map.tilesets.iter().find(|tileset| {
+            !textures
+                .iter()
+                .any(|texture| texture.tileset.first_gid == tileset.first_gid)
+        }) {
+            return Err(TiledError::MissingImage {
+                tileset: tileset.name.clone(),
+            });
+        }
+        for texture in textures.iter() {
+            let embedded_image = extras
+                .tilesets
+                .get(&texture.tileset.first_gid)
+                .and_then(|tileset| tileset.embedded_image.as_ref())
+                .filter(|_| !texture.is_collection);
+            if let Some(image) = embedded_image {
+                tileset_embedded_images.insert(
+                    texture.key,
+                    TiledEmbeddedImage {
+                        handle: Handle::new(),
+                        texture: decode_embedded_image(texture.tileset, image)?,
+                    },
+                );
+            } else {
+                let tileset_path = extras
+                    .tileset_paths
+                    .get(&texture.tileset.first_gid)
+                    .map(|path| path.as_path())
+                    .unwrap_or(asset_path);
+                tileset_image_paths.insert(
+                    texture.key,
+                    resolve_path(tileset_path, &texture.image.source),
+                );
+            }
+            tileset_image_sizes.insert(
+                texture.key,
+                Vec2::new(texture.image.width as f32, texture.image.height as f32),
+            );
+            if !texture.is_collection {
+                // The atlas is only built once the image's texture is loaded while spawning, so
+                // reserve its handle here.
+                tileset_atlases.insert(texture.key, Handle::new());
+            }
+        }
 
-                        let mut i = 0;
-                        for tile in chunk.tiles.iter().flat_map(|tiles_y| tiles_y.iter()) {
-                            if tile.tile_id < tileset_layer.tileset_guid {
-                                continue;
-                            }
+        let mut tile_collisions = HashMap::new();
+        let mut tile_probabilities = HashMap::new();
+        let mut tile_classes = HashMap::new();
+        let mut tile_properties = HashMap::new();
+        let mut tileset_grids = HashMap::new();
+        let mut tileset_transformations = HashMap::new();
+        let mut tileset_wang_sets = HashMap::new();
+        let mut tileset_properties = HashMap::new();
+        for tileset in map.tilesets.iter() {
+            let tileset_extras = extras
+                .tilesets
+                .get(&tileset.first_gid)
+                .cloned()
+                .unwrap_or_default();
+            let grid = tileset_extras.grid.unwrap_or(TiledGrid {
+                orientation: GridOrientation::Orthogonal,
+                size: Vec2::new(tileset.tile_width as f32, tileset.tile_height as f32),
+            });
+            tileset_grids.insert(tileset.first_gid, grid);
+            tileset_transformations.insert(tileset.first_gid, tileset_extras.transformations);
+            tileset_wang_sets.insert(tileset.first_gid, tileset_extras.wang_sets.clone());
+            tileset_properties.insert(tileset.first_gid, tileset_extras.properties.clone());
+            for (tile_id, class) in tileset_extras.tile_classes.iter() {
+                tile_classes.insert(tileset.first_gid + tile_id, class.clone());
+            }
+            for (tile_id, properties) in tileset_extras.tile_properties.iter() {
+                tile_properties.insert(tileset.first_gid + tile_id, properties.clone());
+            }
+            for tile in tileset.tiles.iter() {
+                tile_probabilities.insert(tileset.first_gid + tile.id, tile.probability);
+                if let Some(object_group) = &tile.objectgroup {
+                    let objects = object_group
+                        .objects
+                        .iter()
+                        .map(|object| TiledObject {
+                            properties: tileset_extras
+                                .tile_object_properties
+                                .get(&(tile.id, object.id))
+                                .cloned()
+                                .unwrap_or_default(),
+                            ..TiledObject::from(object)
+                        })
+                        .collect();
+                    tile_collisions.insert(tileset.first_gid + tile.id, objects);
+                }
+            }
+        }
 
-                            // X, Y
-                            positions.push([tile.vertex.x(), tile.vertex.y(), 0.0]);
-                            uvs.push([tile.uv.x(), tile.uv.w()]);
+        let mut tile_animations = HashMap::new();
+        for tileset in map.tilesets.iter() {
+            let image = match tileset.images.first() {
+                Some(image) => image,
+                None => continue,
+            };
+            for tile in tileset.tiles.iter() {
+                if let Some(animation) = &tile.animation {
+                    let frames = animation
+                        .iter()
+                        .map(|frame| (frame.tile_id, frame.duration))
+                        .collect::<Vec<_>>();
+                    let frame_uvs = frames
+                        .iter()
+                        .map(|(tile_id, _)| tile_uv(tileset, image, *tile_id))
+                        .collect();
+                    tile_animations.insert(
+                        tileset.first_gid + tile.id,
+                        TiledTileAnimation { frames, frame_uvs },
+                    );
+                }
+            }
+        }
 
-                            // X, Y + 1
-                            positions.push([tile.vertex.x(), tile.vertex.w(), 0.0]);
-                            uvs.push([tile.uv.x(), tile.uv.y()]);
+        let tile_offsets = extras
+            .tilesets
+            .iter()
+            .map(|(first_gid, tileset)| (*first_gid, tileset.tile_offset))
+            .collect::<HashMap<_, _>>();
 
-                            // X + 1, Y + 1
-                            positions.push([tile.vertex.z(), tile.vertex.w(), 0.0]);
-                            uvs.push([tile.uv.z(), tile.uv.y()]);
+        let mut layers = Vec::new();
 
-                            // X + 1, Y
-                            positions.push([tile.vertex.z(), tile.vertex.y(), 0.0]);
-                            uvs.push([tile.uv.z(), tile.uv.w()]);
+        let tile_size = Vec2::new(map.tile_width as f32, map.tile_height as f32);
 
-                            indices.extend_from_slice(&[i + 0, i + 2, i + 1, i + 0, i + 3, i + 2]);
+        for (layer_index, layer) in map.layers.iter().enumerate() {
+            let layer_extras = extras.layers.get(layer_index).cloned().unwrap_or_default();
+            let offset = Vec2::new(layer_extras.offset.x(), -layer_extras.offset.y());
+
+            // Tiles left behind by a tileset removed in Tiled aren't in any image, so they are
+            // skipped below.
+            if let tiled::LayerData::Finite(rows) = &layer.tiles {
+                let orphans = rows
+                    .iter()
+                    .flatten()
+                    .filter(|tile| {
+                        tile.gid != 0
+                            && !textures
+                                .iter()
+                                .any(|texture| texture.gids.contains(&tile.gid))
+                    })
+                    .count();
+                if orphans > 0 {
+                    warn!(
+                        "skipping {} tiles of layer {:?} in {:?} that aren't in any tileset",
+                        orphans, layer.name, asset_path
+                    );
+                }
+            }
 
-                            i += 4;
-                        }
+            let tileset_layers = build_tileset_layers(
+                &map,
+                layer,
+                &textures,
+                &tile_offsets,
+                offset,
+                extras.hex_layout,
+            )?;
+
+            let layer = Layer {
+                id: layer_extras.id,
+                name: layer.name.clone(),
+                class: layer_extras.class,
+                visible: layer.visible && layer_extras.group_visible,
+                opacity: (layer.opacity * layer_extras.group_opacity).clamp(0.0, 1.0),
+                offset_x: layer_extras.offset.x(),
+                offset_y: layer_extras.offset.y(),
+                tint_color: layer_extras.tint_color,
+                parallax_x: layer_extras.parallax.x(),
+                parallax_y: layer_extras.parallax.y(),
+                properties: layer_extras.properties,
+                tileset_layers,
+            };
+            layers.push(layer);
+        }
 
-                        if positions.len() > 0 {
-                            let mesh = Mesh {
-                                primitive_topology: PrimitiveTopology::TriangleList,
-                                attributes: vec![
-                                    VertexAttribute::position(positions),
-                                    VertexAttribute::uv(uvs),
-                                ],
-                                indices: Some(indices),
-                            };
-                            meshes.push((layer_id as u32, tileset_layer.tileset_guid, mesh));
+        let object_layers = map
+            .object_groups
+            .iter()
+            .enumerate()
+            .map(|(object_layer_index, object_group)| {
+                let mut object_layer = TiledObjectLayer::from(object_group);
+                if let Some(object_layer_extras) = extras.object_layers.get(object_layer_index) {
+                    object_layer.id = object_layer_extras.id;
+                    object_layer.class = object_layer_extras.class.clone();
+                    object_layer.draw_order = object_layer_extras.draw_order;
+                    object_layer.properties = object_layer_extras.properties.clone();
+                    object_layer.visible &= object_layer_extras.group_visible;
+                    object_layer.opacity *= object_layer_extras.group_opacity;
+                }
+                for object in object_layer.objects.iter_mut() {
+                    if let Some(object_extras) = extras.objects.get(&object.id) {
+                        object.class = object_extras.class.clone();
+                        object.properties = object_extras.properties.clone();
+                        object.visible = object_extras.visible;
+                        if let Some(text) = &object_extras.text {
+                            object.shape = TiledObjectShape::Text(text.clone());
                         }
                     }
                 }
-            }
+                object_layer
+            })
+            .collect();
+
+        if map.image_layers.len() != extras.image_layers.len() {
+            return Err(TiledError::ImageLayerCountMismatch {
+                expected: extras.image_layers.len(),
+                found: map.image_layers.len(),
+            });
         }
+        let image_layers = map
+            .image_layers
+            .iter()
+            .zip(extras.image_layers.iter())
+            .map(|(image_layer, image_layer_extras)| {
+                TiledImageLayer {
+                    id: image_layer_extras.id,
+                    name: image_layer.name.clone(),
+                    class: image_layer_extras.class.clone(),
+                    image: image_layer
+                        .image
+                        .as_ref()
+                        .map(|image| resolve_path(asset_path, &image.source)),
+                    size: image_layer
+                        .image
+                        .as_ref()
+                        .map(|image| Vec2::new(image.width as f32, image.height as f32))
+                        .unwrap_or_else(Vec2::zero),
+                    offset: image_layer_extras.offset,
+                    tint_color: image_layer_extras.tint_color,
+                    repeat_x: image_layer_extras.repeat_x,
+                    repeat_y: image_layer_extras.repeat_y,
+                    opacity: (image_layer.opacity * image_layer_extras.group_opacity)
+                        .clamp(0.0, 1.0),
+                    visible: image_layer.visible && image_layer_extras.group_visible,
+                    // Image layers are drawn halfway between the tile layers around them.
+                    z: image_layer_extras.tile_layers_before as f32 - 0.5,
+                    properties: image_layer_extras.properties.clone(),
+                }
+            })
+            .collect();
 
         let map = Map {
             map,
-            meshes,
             layers,
+            object_layers,
+            image_layers,
+            layer_tree: extras.layer_tree,
             tile_size,
-            image_folder: asset_path.parent().unwrap().to_str().unwrap().to_string(),
+            hex_layout: extras.hex_layout,
+            render_order: extras.render_order,
+            background_color: extras.background_color,
+            tile_animations,
+            tile_collisions,
+            tile_offsets,
+            tile_probabilities,
+            tile_classes,
+            tile_properties,
+            tileset_grids,
+            tileset_transformations,
+            tileset_wang_sets,
+            tileset_properties,
+            class: extras.class,
+            tiled_version: extras.tiled_version,
+            next_layer_id: extras.next_layer_id,
+            next_object_id: extras.next_object_id,
+            editor_chunk_size: extras.editor_chunk_size,
+            properties: extras.properties,
+            tileset_image_paths,
+            tileset_image_sizes,
+            tileset_embedded_images,
+            tileset_atlases,
+            document,
+            changed_tiles: Vec::new(),
+            image_folder: asset_path
+                .parent()
+                .and_then(Path::to_str)
+                .ok_or_else(|| TiledError::InvalidPath(asset_path.to_path_buf()))?
+                .to_string(),
         };
 
         Ok(map)
     }
+}
+
+impl AssetLoader<Map> for TiledMapLoader {
+    fn from_bytes(&self, asset_path: &Path, bytes: Vec<u8>) -> anyhow::Result<Map> {
+        TiledMapLoader::load_map(asset_path, bytes)
+            .with_context(|| format!("failed to load tiled map {:?}", asset_path))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        static EXTENSIONS: &[&str] = &["tmx", "tmj"];
+        EXTENSIONS
+    }
+}
+
+/// Loads `.json` maps, which only `TiledMapPlugin::load_json_maps` registers as other `.json`
+/// assets can't be loaded once it is.
+#[derive(Default)]
+pub(crate) struct TiledJsonMapLoader;
+
+impl AssetLoader<Map> for TiledJsonMapLoader {
+    fn from_bytes(&self, asset_path: &Path, bytes: Vec<u8>) -> anyhow::Result<Map> {
+        TiledMapLoader.from_bytes(asset_path, bytes)
+    }
 
     fn extensions(&self) -> &[&str] {
-        static EXTENSIONS: &[&str] = &["tmx"];
+        static EXTENSIONS: &[&str] = &["json"];
         EXTENSIONS
     }
 }
@@ -1,21 +1,78 @@
 use bevy::{
+    asset::LoadState,
+    ecs::ResourcesWriter,
     prelude::*,
     render::{
-        pipeline::{DynamicBinding, PipelineSpecialization, RenderPipeline},
+        mesh::VertexAttribute,
+        pipeline::{DynamicBinding, PipelineSpecialization, PrimitiveTopology, RenderPipeline},
         render_graph::base::MainPass,
     },
 };
 
-use crate::{TileMapChunk, TILE_MAP_PIPELINE_HANDLE};
+use crate::{
+    colliders::merge_tile_rects,
+    element::Element,
+    loader::{
+        build_tileset_chunks, build_tileset_layers, tileset_columns, tileset_rows,
+        tileset_textures, TiledMapLoader, ALL_FLIP_FLAGS, CHUNK_TILES, FLIPPED_DIAGONALLY_FLAG,
+        FLIPPED_HORIZONTALLY_FLAG, FLIPPED_VERTICALLY_FLAG,
+    },
+    AnimatedTile, AnimatedTileSprite, AnimatedTiles, RenderChunk, TileGrid, TileMapChunk, TileRect,
+    TiledCollider, TiledError, TiledImageLayer, TiledLayerParallax, TiledLayerVisibility,
+    TiledMapColliders, TiledObject, TiledObjectLayer, TiledProperties, TiledTileAnimation,
+    TiledWangSet, TILE_MAP_PIPELINE_HANDLE,
+};
+use anyhow::{bail, Result};
 use glam::Vec2;
-use std::collections::{HashMap, HashSet};
+use log::warn;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    io::Write,
+    path::{Path, PathBuf},
+};
 
 #[derive(Debug)]
 pub struct Tile {
     pub tile_id: u32,
     pub pos: Vec2,
     pub vertex: Vec4,
+    /// The tile's rect in the tileset image, before any flips are applied.
     pub uv: Vec4,
+    pub flip_h: bool,
+    pub flip_v: bool,
+    pub flip_d: bool,
+}
+
+impl Tile {
+    /// Returns the UVs of the bottom left, top left, top right and bottom right corners.
+    ///
+    /// Like Tiled, the diagonal flip is applied first, followed by the horizontal and vertical
+    /// flips.
+    pub fn corner_uvs(&self) -> [[f32; 2]; 4] {
+        flipped_corner_uvs(self.uv, self.flip_h, self.flip_v, self.flip_d)
+    }
+}
+
+fn flipped_corner_uvs(uv: Vec4, flip_h: bool, flip_v: bool, flip_d: bool) -> [[f32; 2]; 4] {
+    let mut top_left = [uv.x(), uv.y()];
+    let mut top_right = [uv.z(), uv.y()];
+    let mut bottom_left = [uv.x(), uv.w()];
+    let mut bottom_right = [uv.z(), uv.w()];
+
+    if flip_d {
+        std::mem::swap(&mut top_right, &mut bottom_left);
+    }
+    if flip_h {
+        std::mem::swap(&mut top_left, &mut top_right);
+        std::mem::swap(&mut bottom_left, &mut bottom_right);
+    }
+    if flip_v {
+        std::mem::swap(&mut top_left, &mut bottom_left);
+        std::mem::swap(&mut top_right, &mut bottom_right);
+    }
+
+    [bottom_left, top_left, top_right, bottom_right]
 }
 
 #[derive(Debug)]
@@ -33,20 +90,461 @@ pub struct TilesetLayer {
 
 #[derive(Debug)]
 pub struct Layer {
+    /// The layer's id, unique across the map and kept when layers are renamed or moved. Maps
+    /// saved before Tiled 1.2 don't have layer ids.
+    pub id: Option<u32>,
+    pub name: String,
+    pub class: Option<String>,
+    /// Whether the layer and all of its groups are visible. Hidden layers are only spawned when
+    /// the map entity's `TiledMapSpawnInvisibleLayers` is enabled.
+    pub visible: bool,
+    /// The layer's opacity, from 0.0 to 1.0, multiplied with the opacity of its groups.
+    pub opacity: f32,
+    /// The layer's offset in pixels, which is already applied to its tiles. Like in Tiled,
+    /// positive y moves the layer down.
+    pub offset_x: f32,
+    pub offset_y: f32,
+    /// The color the layer's tiles are multiplied with, including the tints of its groups.
+    pub tint_color: Option<Color>,
+    /// How fast the layer scrolls relative to the camera, see `TiledLayerParallax`. Includes
+    /// the parallax factors of the layer's groups.
+    pub parallax_x: f32,
+    pub parallax_y: f32,
+    pub properties: TiledProperties,
     pub tileset_layers: Vec<TilesetLayer>,
 }
 
+impl Layer {
+    /// Returns the color the layer's tiles are drawn with, combining its tint and opacity.
+    pub fn color(&self) -> Color {
+        let tint_color = self.tint_color.unwrap_or(Color::WHITE);
+        Color::rgba(
+            tint_color.r,
+            tint_color.g,
+            tint_color.b,
+            tint_color.a * self.opacity,
+        )
+    }
+}
+
+/// A layer in the map's layer hierarchy, as the layers are organized in Tiled.
+#[derive(Debug, Clone)]
+pub enum LayerKind {
+    /// A tile layer, indexing the parsed `tiled` map's `layers`.
+    Tile(usize),
+    /// An object layer, indexing `Map::object_layers`.
+    Object(usize),
+    /// An image layer, indexing `Map::image_layers`.
+    Image(usize),
+    Group(TiledGroupLayer),
+}
+
+/// A group layer and the layers nested in it.
+#[derive(Debug, Clone)]
+pub struct TiledGroupLayer {
+    pub id: Option<u32>,
+    pub name: String,
+    pub class: Option<String>,
+    pub visible: bool,
+    pub opacity: f32,
+    /// The group's offset in pixels, with y pointing down.
+    pub offset: Vec2,
+    pub tint_color: Option<Color>,
+    pub parallax: Vec2,
+    pub properties: TiledProperties,
+    pub children: Vec<LayerKind>,
+}
+
+/// Replaces the data of the `<layer>` elements nested in `element` with the tiles of `layers`,
+/// which `tiled` lists in document order.
+fn encode_tile_layers<'a>(
+    element: &mut Element,
+    layers: &mut impl Iterator<Item = &'a tiled::Layer>,
+) -> Result<()> {
+    for child in element.children.iter_mut() {
+        match child.name.as_str() {
+            "layer" => {
+                let layer = match layers.next() {
+                    Some(layer) => layer,
+                    None => return Ok(()),
+                };
+                let tiles = match &layer.tiles {
+                    tiled::LayerData::Finite(tiles) => tiles,
+                    _ => bail!("infinite maps are not supported"),
+                };
+                let mut bytes = Vec::new();
+                for tile in tiles.iter().flatten() {
+                    let mut gid = tile.gid;
+                    if tile.flip_h {
+                        gid |= FLIPPED_HORIZONTALLY_FLAG;
+                    }
+                    if tile.flip_v {
+                        gid |= FLIPPED_VERTICALLY_FLAG;
+                    }
+                    if tile.flip_d {
+                        gid |= FLIPPED_DIAGONALLY_FLAG;
+                    }
+                    bytes.extend_from_slice(&gid.to_le_bytes());
+                }
+                let mut encoder = libflate::zlib::Encoder::new(Vec::new())?;
+                encoder.write_all(&bytes)?;
+                let compressed = encoder.finish().into_result()?;
+
+                let mut data = Element {
+                    name: "data".to_string(),
+                    text: base64::encode(&compressed),
+                    ..Default::default()
+                };
+                data.set_attribute("encoding", "base64".to_string());
+                data.set_attribute("compression", "zlib".to_string());
+                child.children.retain(|child| child.name != "data");
+                child.children.push(data);
+            }
+            "group" => encode_tile_layers(child, layers)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Returns the transform that flips a sprite centered on the origin like Tiled flips tiles.
+fn flip_transform(flip_h: bool, flip_v: bool, flip_d: bool) -> Mat4 {
+    // Like for tile meshes, the diagonal flip is applied before the horizontal and vertical
+    // flips. As y points up here, flipping across the diagonal swaps x and -y.
+    let flip_d = if flip_d {
+        Mat4::from_cols(
+            Vec4::new(0.0, -1.0, 0.0, 0.0),
+            Vec4::new(-1.0, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, 0.0, 1.0, 0.0),
+            Vec4::new(0.0, 0.0, 0.0, 1.0),
+        )
+    } else {
+        Mat4::identity()
+    };
+    let flip_hv = Mat4::from_scale(Vec3::new(
+        if flip_h { -1.0 } else { 1.0 },
+        if flip_v { -1.0 } else { 1.0 },
+        1.0,
+    ));
+    flip_hv * flip_d
+}
+
+/// Collects tile quads into meshes, starting a new mesh whenever the vertex count would
+/// overflow the 16 bit index buffers bevy uploads.
+#[derive(Default)]
+struct ChunkMeshBuilder {
+    positions: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    indices: Vec<u32>,
+    animated_tiles: Vec<AnimatedTile>,
+    meshes: Vec<(Mesh, Vec<AnimatedTile>, RenderChunk)>,
+}
+
+impl ChunkMeshBuilder {
+    const MAX_VERTICES: usize = u16::MAX as usize + 1;
+
+    fn add_chunk(&mut self, map: &Map, chunk: &Chunk, tileset_guid: u32, z: f32) {
+        let mut tiles = chunk
+            .tiles
+            .iter()
+            .flat_map(|tiles_y| tiles_y.iter())
+            .collect::<Vec<_>>();
+        tiles.sort_by_key(|tile| map.render_order.sort_key(tile.pos));
+
+        for tile in tiles {
+            if tile.tile_id < tileset_guid {
+                continue;
+            }
+            if self.positions.len() + 4 > Self::MAX_VERTICES {
+                self.flush();
+            }
+            let i = self.positions.len() as u32;
+
+            // X, Y
+            self.positions.push([tile.vertex.x(), tile.vertex.y(), z]);
+            // X, Y + 1
+            self.positions.push([tile.vertex.x(), tile.vertex.w(), z]);
+            // X + 1, Y + 1
+            self.positions.push([tile.vertex.z(), tile.vertex.w(), z]);
+            // X + 1, Y
+            self.positions.push([tile.vertex.z(), tile.vertex.y(), z]);
+
+            match map.tile_animations.get(&tile.tile_id) {
+                Some(animation) => {
+                    let frame_corner_uvs = animation
+                        .frame_uvs
+                        .iter()
+                        .map(|uv| flipped_corner_uvs(*uv, tile.flip_h, tile.flip_v, tile.flip_d))
+                        .collect::<Vec<_>>();
+                    self.uvs.extend_from_slice(&frame_corner_uvs[0]);
+                    self.animated_tiles.push(AnimatedTile {
+                        vertex_index: i as usize,
+                        animation: animation.clone(),
+                        frame_corner_uvs,
+                        current_frame: 0,
+                    });
+                }
+                None => self.uvs.extend_from_slice(&tile.corner_uvs()),
+            }
+
+            self.indices
+                .extend_from_slice(&[i, i + 2, i + 1, i, i + 3, i + 2]);
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.positions.is_empty() {
+            return;
+        }
+        let bounds = RenderChunk::from_positions(&self.positions);
+        let mesh = Mesh {
+            primitive_topology: PrimitiveTopology::TriangleList,
+            attributes: vec![
+                VertexAttribute::position(std::mem::take(&mut self.positions)),
+                VertexAttribute::uv(std::mem::take(&mut self.uvs)),
+            ],
+            indices: Some(std::mem::take(&mut self.indices)),
+        };
+        self.meshes
+            .push((mesh, std::mem::take(&mut self.animated_tiles), bounds));
+    }
+
+    fn finish(mut self) -> Vec<(Mesh, Vec<AnimatedTile>, RenderChunk)> {
+        self.flush();
+        self.meshes
+    }
+}
+
+/// The order tiles are drawn in, which decides how overlapping tiles stack.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RenderOrder {
+    #[default]
+    RightDown,
+    RightUp,
+    LeftDown,
+    LeftUp,
+}
+
+impl RenderOrder {
+    /// Sorts tiles row by row, where `pos` is the tile's position with y pointing down.
+    fn sort_key(self, pos: Vec2) -> (i32, i32) {
+        let (x, y) = (pos.x() as i32, pos.y() as i32);
+        match self {
+            RenderOrder::RightDown => (y, x),
+            RenderOrder::RightUp => (-y, x),
+            RenderOrder::LeftDown => (y, -x),
+            RenderOrder::LeftUp => (-y, -x),
+        }
+    }
+}
+
+/// Which cells count as a tile's neighbors in `Map::neighbors`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TileNeighborhood {
+    /// The cells sharing an edge with the tile.
+    Four,
+    /// The cells sharing an edge or a corner with the tile.
+    Eight,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StaggerAxis {
+    X,
+    Y,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StaggerIndex {
+    Odd,
+    Even,
+}
+
+/// The shape of a hexagonal or staggered map's grid.
+///
+/// Every other row (for the `Y` axis) or column (for the `X` axis) is shifted by half a tile,
+/// starting with the odd or even ones depending on the stagger index. Staggered maps are laid out
+/// like hexagonal maps with a side length of 0, so their diamond tiles interlock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HexLayout {
+    /// The length of the flat side of each hexagon in pixels, 0 for staggered maps.
+    pub side_length: u32,
+    pub stagger_axis: StaggerAxis,
+    pub stagger_index: StaggerIndex,
+}
+
+impl Default for HexLayout {
+    fn default() -> Self {
+        Self {
+            side_length: 0,
+            stagger_axis: StaggerAxis::Y,
+            stagger_index: StaggerIndex::Odd,
+        }
+    }
+}
+
+impl HexLayout {
+    /// Returns the distance between neighboring columns and rows of the grid, ignoring the half
+    /// tile shifts.
+    fn cell_step(&self, tile_width: f32, tile_height: f32) -> Vec2 {
+        let side_length = self.side_length as f32;
+        match self.stagger_axis {
+            StaggerAxis::X => Vec2::new((tile_width + side_length) / 2.0, tile_height),
+            StaggerAxis::Y => Vec2::new(tile_width, (tile_height + side_length) / 2.0),
+        }
+    }
+
+    fn is_staggered(&self, index: f32) -> bool {
+        let is_odd = index as i32 % 2 != 0;
+        match self.stagger_index {
+            StaggerIndex::Odd => is_odd,
+            StaggerIndex::Even => !is_odd,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GridOrientation {
+    Orthogonal,
+    Isometric,
+}
+
+/// The grid of a tileset, set with its `<grid>` element.
+///
+/// Tiled uses the grid to draw terrain and collision overlays on the tiles of isometric
+/// tilesets. Like in Tiled, it doesn't change where tiles are placed on the map.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TiledGrid {
+    pub orientation: GridOrientation,
+    /// The size of a grid cell in pixels.
+    pub size: Vec2,
+}
+
+/// The transformations Tiled may apply to a tileset's tiles when autotiling with its wang sets,
+/// set with its `<transformations>` element. All of them are off by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TiledTransformations {
+    /// Tiles can be flipped horizontally.
+    pub hflip: bool,
+    /// Tiles can be flipped vertically.
+    pub vflip: bool,
+    /// Tiles can be rotated in 90 degree steps.
+    pub rotate: bool,
+    /// Tiles that fit without being transformed are preferred over transformed ones.
+    pub prefer_untransformed: bool,
+}
+
+/// A mesh for the tiles of one tileset within a chunk.
+pub struct ChunkMesh {
+    pub layer_id: u32,
+    pub position: TiledChunkPosition,
+    pub tileset_guid: u32,
+    pub mesh: Mesh,
+    /// The animated tiles in the mesh.
+    pub animated_tiles: Vec<AnimatedTile>,
+    /// The bounds of the mesh's vertices.
+    pub bounds: RenderChunk,
+}
+
+/// An image embedded in a tileset, decoded when the map is loaded.
+#[derive(Clone)]
+pub struct TiledEmbeddedImage {
+    /// The texture is added to `Assets<Texture>` under this handle when the map is spawned.
+    pub handle: Handle<Texture>,
+    pub texture: Texture,
+}
+
+impl fmt::Debug for TiledEmbeddedImage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TiledEmbeddedImage")
+            .field("handle", &self.handle)
+            .field("size", &self.texture.size)
+            .finish()
+    }
+}
+
 // An asset for maps
 #[derive(Debug)]
 pub struct Map {
     pub map: tiled::Map,
-    pub meshes: Vec<(u32, u32, Mesh)>,
+    /// The map's class, or its `type` in maps saved before Tiled 1.9.
+    pub class: Option<String>,
+    /// The version of Tiled the map was last saved with, which can differ from the format
+    /// version in `map.version`. Maps saved before Tiled 1.0 don't have one.
+    pub tiled_version: Option<String>,
+    /// The id Tiled gives the next layer added to the map, which is above the ids of all of its
+    /// layers.
+    pub next_layer_id: Option<u32>,
+    /// The id Tiled gives the next object added to the map, which is above the ids of all of its
+    /// objects.
+    pub next_object_id: Option<u32>,
+    /// The size in tiles of the chunks Tiled saves infinite maps in, from the map's
+    /// `<editorsettings>`. Tiled uses 16 by 16 tiles for maps that don't set it.
+    pub editor_chunk_size: Option<(u32, u32)>,
     pub layers: Vec<Layer>,
+    pub object_layers: Vec<TiledObjectLayer>,
+    pub image_layers: Vec<TiledImageLayer>,
+    /// All of the map's layers, nested in their groups.
+    pub layer_tree: Vec<LayerKind>,
     pub tile_size: Vec2,
+    pub hex_layout: HexLayout,
+    pub render_order: RenderOrder,
+    pub background_color: Option<Color>,
+    /// The animations of animated tiles, keyed by gid.
+    pub tile_animations: HashMap<u32, TiledTileAnimation>,
+    /// The collision shapes drawn on tiles in the tileset's collision editor, keyed by gid.
+    ///
+    /// Object positions are relative to the top left corner of the tile.
+    pub tile_collisions: HashMap<u32, Vec<TiledObject>>,
+    /// The offset each tileset's tiles are drawn at in pixels, keyed by first gid. Like in
+    /// Tiled, positive y moves tiles down.
+    pub tile_offsets: HashMap<u32, Vec2>,
+    /// How likely Tiled's random brush picks each tile listed in the tilesets, keyed by gid.
+    pub tile_probabilities: HashMap<u32, f32>,
+    /// The classes of tiles in the tilesets, keyed by gid.
+    pub tile_classes: HashMap<u32, String>,
+    /// The custom properties of tiles in the tilesets, keyed by gid.
+    pub tile_properties: HashMap<u32, TiledProperties>,
+    /// The grid of each tileset, keyed by first gid. Tilesets without a `<grid>` element have
+    /// an orthogonal grid of their tile size.
+    pub tileset_grids: HashMap<u32, TiledGrid>,
+    /// The transformations allowed on each tileset's tiles, keyed by first gid.
+    pub tileset_transformations: HashMap<u32, TiledTransformations>,
+    /// The wang sets of each tileset, keyed by first gid.
+    pub tileset_wang_sets: HashMap<u32, Vec<TiledWangSet>>,
+    /// The custom properties of each tileset, keyed by first gid.
+    pub tileset_properties: HashMap<u32, TiledProperties>,
+    /// The map's own custom properties.
+    pub properties: TiledProperties,
+    /// The resolved image path of each tileset, keyed by first gid. Tiles from image collection
+    /// tilesets have their own image, keyed by the tile's gid.
+    pub tileset_image_paths: HashMap<u32, PathBuf>,
+    /// The size in pixels of each image in `tileset_image_paths`, with the same keys. The images
+    /// of tiles in an image collection tileset may each have a different size.
+    pub tileset_image_sizes: HashMap<u32, Vec2>,
+    /// The images embedded in tilesets instead of read from a file, keyed by first gid. These
+    /// tilesets aren't in `tileset_image_paths`.
+    pub tileset_embedded_images: HashMap<u32, TiledEmbeddedImage>,
+    /// The texture atlas of each tileset with a single image, keyed by first gid. The atlases are
+    /// added to `Assets<TextureAtlas>` when the map is first spawned. See `atlas_for_tileset`.
+    pub tileset_atlases: HashMap<u32, Handle<TextureAtlas>>,
     pub image_folder: String,
+    /// The map's document as it was loaded, with its tilesets still referenced.
+    pub(crate) document: Element,
+    /// The tiles changed with `set_tile` that haven't been respawned yet.
+    pub(crate) changed_tiles: Vec<TileChange>,
 }
 
 impl Map {
+    /// Parses a map held in memory, such as one downloaded at runtime. Add it to `Assets<Map>`
+    /// and spawn a `TiledMapComponents` with its handle to draw it like a loaded map.
+    ///
+    /// `path` is where the map's file would be. External tilesets are read from files and images
+    /// are loaded by the asset server relative to it, like for a map loaded from `path`, so they
+    /// still have to exist there. A `.tmj` or `.json` extension parses the bytes as a JSON map.
+    pub fn from_bytes(path: impl AsRef<Path>, bytes: Vec<u8>) -> Result<Map, TiledError> {
+        TiledMapLoader::load_map(path.as_ref(), bytes)
+    }
+
     pub fn project_ortho(pos: Vec2, tile_width: f32, tile_height: f32) -> Vec2 {
         let x = tile_width * pos.x();
         let y = tile_height * pos.y();
@@ -69,6 +567,39 @@ impl Map {
         let y = ((-(pos.y()) / half_height) - (pos.x() / half_width)) / 2.0;
         Vec2::new(x.round(), y.round())
     }
+    /// Projects a cell of a hexagonal or staggered map, see `HexLayout`.
+    pub fn project_hex(pos: Vec2, tile_width: f32, tile_height: f32, layout: HexLayout) -> Vec2 {
+        let step = layout.cell_step(tile_width, tile_height);
+        let (x, y) = match layout.stagger_axis {
+            StaggerAxis::X => {
+                let mut y = pos.y() * tile_height;
+                if layout.is_staggered(pos.x()) {
+                    y += tile_height / 2.0;
+                }
+                (pos.x() * step.x(), y)
+            }
+            StaggerAxis::Y => {
+                let mut x = pos.x() * tile_width;
+                if layout.is_staggered(pos.y()) {
+                    x += tile_width / 2.0;
+                }
+                (x, pos.y() * step.y())
+            }
+        };
+        Vec2::new(x, -y)
+    }
+    /// Returns the transform the map is drawn with when its `anchor` is placed at `origin`.
+    /// Positions returned by methods like `tile_to_world` are relative to this transform.
+    pub fn anchor_transform(&self, origin: Transform, anchor: TiledMapAnchor) -> Transform {
+        match anchor {
+            TiledMapAnchor::TopLeft => {
+                let top_left = self.pixel_to_world(Vec2::zero());
+                Transform::new(*origin.value() * Mat4::from_translation(-top_left.extend(0.0)))
+            }
+            TiledMapAnchor::FirstTileCenter => origin,
+        }
+    }
+
     pub fn center(&self, origin: Transform) -> Transform {
         let tile_size = Vec2::new(self.map.tile_width as f32, self.map.tile_height as f32);
         let map_center = Vec2::new(self.map.width as f32 / 2.0, self.map.height as f32 / 2.0);
@@ -81,31 +612,1521 @@ impl Map {
                 let center = Map::project_iso(map_center, tile_size.x(), tile_size.y());
                 Transform::new(*origin.value() * Mat4::from_translation(-center.extend(0.0)))
             }
+            tiled::Orientation::Hexagonal | tiled::Orientation::Staggered => {
+                // The half tile offsets mean the map's center can't be projected directly, use the
+                // far corner of the grid instead.
+                let far_corner = Map::project_hex(
+                    Vec2::new(self.map.width as f32, self.map.height as f32),
+                    tile_size.x(),
+                    tile_size.y(),
+                    self.hex_layout,
+                );
+                let center = far_corner / 2.0;
+                Transform::new(*origin.value() * Mat4::from_translation(-center.extend(0.0)))
+            }
+        }
+    }
+
+    /// Returns the width and height of the map in tiles.
+    pub fn size_in_tiles(&self) -> (u32, u32) {
+        (self.map.width, self.map.height)
+    }
 
-            _ => panic!("Unsupported orientation {:?}", self.map.orientation),
+    /// Returns the size of the rendered map in pixels, like Tiled shows it, not counting tiles
+    /// larger than the grid or tile offsets.
+    ///
+    /// Isometric maps are diamonds whose rows and columns both run diagonally, so each tile along
+    /// either axis adds half a tile to the width and height: `(width + height) * tile_size / 2`.
+    /// Staggered and hexagonal maps add half a tile along the stagger axis for the shifted rows
+    /// or columns, and their rows or columns overlap by the part of the tile outside of the
+    /// hexagon's flat side.
+    pub fn size_in_pixels(&self) -> Vec2 {
+        let (width, height) = (self.map.width as f32, self.map.height as f32);
+        let tile_size = self.tile_size;
+        match self.map.orientation {
+            tiled::Orientation::Isometric => (width + height) * tile_size / 2.0,
+            tiled::Orientation::Hexagonal | tiled::Orientation::Staggered => {
+                let step = self.hex_layout.cell_step(tile_size.x(), tile_size.y());
+                match self.hex_layout.stagger_axis {
+                    StaggerAxis::X => {
+                        let mut size = Vec2::new(
+                            width * step.x() + tile_size.x() - step.x(),
+                            height * tile_size.y(),
+                        );
+                        if self.map.width > 1 {
+                            *size.y_mut() += tile_size.y() / 2.0;
+                        }
+                        size
+                    }
+                    StaggerAxis::Y => {
+                        let mut size = Vec2::new(
+                            width * tile_size.x(),
+                            height * step.y() + tile_size.y() - step.y(),
+                        );
+                        if self.map.height > 1 {
+                            *size.x_mut() += tile_size.x() / 2.0;
+                        }
+                        size
+                    }
+                }
+            }
+            tiled::Orientation::Orthogonal => Vec2::new(width, height) * tile_size,
+        }
+    }
+
+    /// Returns the center of a map cell, relative to the map's transform.
+    pub fn tile_to_world(&self, x: u32, y: u32) -> Vec2 {
+        let grid_position = Vec2::new(x as f32, y as f32);
+        let tile_size = self.tile_size;
+        match self.map.orientation {
+            tiled::Orientation::Isometric => {
+                Map::project_iso(grid_position, tile_size.x(), tile_size.y())
+            }
+            tiled::Orientation::Hexagonal | tiled::Orientation::Staggered => {
+                Map::project_hex(grid_position, tile_size.x(), tile_size.y(), self.hex_layout)
+            }
+            _ => Map::project_ortho(grid_position, tile_size.x(), tile_size.y()),
+        }
+    }
+
+    /// Returns the map cell containing a position relative to the map's transform, or `None` if
+    /// the position is outside the map.
+    pub fn world_to_tile(&self, world: Vec2) -> Option<(u32, u32)> {
+        let tile_size = self.tile_size;
+        let (x, y) = match self.map.orientation {
+            tiled::Orientation::Isometric => {
+                let tile = Map::unproject_iso(world, tile_size.x(), tile_size.y());
+                (tile.x() as i64, tile.y() as i64)
+            }
+            tiled::Orientation::Hexagonal | tiled::Orientation::Staggered => {
+                // Hexagons are the cells closest to their center, so search the cells around
+                // the rough grid position for the closest one. Staggered diamonds are measured
+                // in units of their half size instead, so that the distance is 1 on their edges.
+                let step = self.hex_layout.cell_step(tile_size.x(), tile_size.y());
+                let rough = Map::unproject_ortho(world, step.x(), step.y());
+                let (rough_x, rough_y) = (rough.x().round() as i64, rough.y().round() as i64);
+                let staggered = self.map.orientation == tiled::Orientation::Staggered;
+                let mut closest = None;
+                for y in rough_y - 1..=rough_y + 1 {
+                    for x in rough_x - 1..=rough_x + 1 {
+                        let center = Map::project_hex(
+                            Vec2::new(x as f32, y as f32),
+                            tile_size.x(),
+                            tile_size.y(),
+                            self.hex_layout,
+                        );
+                        let delta = center - world;
+                        let distance = if staggered {
+                            let delta = delta.abs() / (tile_size / 2.0);
+                            delta.x() + delta.y()
+                        } else {
+                            delta.length_squared()
+                        };
+                        if closest.is_none_or(|(_, closest)| distance < closest) {
+                            closest = Some(((x, y), distance));
+                        }
+                    }
+                }
+                closest?.0
+            }
+            _ => {
+                let tile = Map::unproject_ortho(world, tile_size.x(), tile_size.y());
+                (tile.x().round() as i64, tile.y().round() as i64)
+            }
+        };
+        if x < 0 || y < 0 || x >= self.map.width as i64 || y >= self.map.height as i64 {
+            return None;
+        }
+        Some((x as u32, y as u32))
+    }
+
+    /// Returns the tiles at a position relative to the map's transform as `(layer_index, gid)`,
+    /// from the top layer down. `layer_index` is interpreted like in `get_tile`.
+    ///
+    /// Each layer's offset is taken into account, but not parallax scrolling. Empty cells are
+    /// skipped, while hidden layers are included: check `layers[layer_index].visible` to skip
+    /// them.
+    pub fn pick_tile(&self, world: Vec2) -> Vec<(usize, u32)> {
+        self.layers
+            .iter()
+            .enumerate()
+            .rev()
+            .filter_map(|(layer_index, layer)| {
+                let offset = Vec2::new(layer.offset_x, -layer.offset_y);
+                let (x, y) = self.world_to_tile(world - offset)?;
+                match self.get_tile(layer_index, x, y)? {
+                    0 => None,
+                    gid => Some((layer_index, gid)),
+                }
+            })
+            .collect()
+    }
+
+    /// Converts a position in map pixels, which is how object positions are stored, to a
+    /// position relative to the map's transform.
+    pub fn pixel_to_world(&self, pos: Vec2) -> Vec2 {
+        let tile_size = self.tile_size;
+        match self.map.orientation {
+            tiled::Orientation::Isometric => {
+                // Isometric maps measure both axes of their pixel space along the tile rows, in
+                // units of the tile height. The origin is the top corner of the first tile.
+                let grid_position = pos / tile_size.y();
+                Map::project_iso(grid_position, tile_size.x(), tile_size.y())
+                    + Vec2::new(0.0, tile_size.y() / 2.0)
+            }
+            // The origin is the top left corner of the first tile.
+            _ => Vec2::new(pos.x() - tile_size.x() / 2.0, tile_size.y() / 2.0 - pos.y()),
+        }
+    }
+
+    /// Converts a position in pixels of the rendered map, which is how image layer offsets are
+    /// stored, to a position relative to the map's transform.
+    pub fn screen_to_world(&self, pos: Vec2) -> Vec2 {
+        let tile_size = self.tile_size;
+        let origin = match self.map.orientation {
+            // The leftmost point of an isometric map is the left corner of the last row's first
+            // tile.
+            tiled::Orientation::Isometric => Vec2::new(
+                -(self.map.height as f32) * tile_size.x() / 2.0,
+                tile_size.y() / 2.0,
+            ),
+            _ => Vec2::new(-tile_size.x() / 2.0, tile_size.y() / 2.0),
+        };
+        origin + Vec2::new(pos.x(), -pos.y())
+    }
+
+    /// Returns the transform of a sprite drawing a tile object's `image_size` tile, relative to
+    /// the map's transform. The tile is scaled to the object's size, and flipped and rotated like
+    /// in Tiled.
+    pub fn tile_object_sprite_transform(
+        &self,
+        object: &TiledObject,
+        image_size: Vec2,
+        z: f32,
+    ) -> Mat4 {
+        // Sprites are centered on their transform.
+        let center = match self.map.orientation {
+            tiled::Orientation::Isometric => Vec2::new(0.0, object.size.y() / 2.0),
+            _ => object.size / 2.0,
+        };
+
+        // The diagonal flip swaps the image's axes, so scale it to the transposed size first.
+        let size = if object.flip_d {
+            Vec2::new(object.size.y(), object.size.x())
+        } else {
+            object.size
+        };
+        let scale = Mat4::from_scale((size / image_size).extend(1.0));
+        self.object_transform(object, z)
+            * Mat4::from_translation(center.extend(0.0))
+            * flip_transform(object.flip_h, object.flip_v, object.flip_d)
+            * scale
+    }
+
+    /// Returns the transform of an object entity relative to the map's transform. It is placed
+    /// at the object's position and rotated around it like in Tiled.
+    pub fn object_transform(&self, object: &TiledObject, z: f32) -> Mat4 {
+        let position = self.pixel_to_world(object.position);
+        // Tiled rotates clockwise, bevy counterclockwise.
+        let rotation = Quat::from_rotation_z(-object.rotation.to_radians());
+        Mat4::from_rotation_translation(rotation, position.extend(z))
+    }
+
+    /// Iterates every object in the map along with the object layer it belongs to.
+    pub fn all_objects(&self) -> impl Iterator<Item = (&TiledObjectLayer, &TiledObject)> {
+        self.object_layers.iter().flat_map(|object_layer| {
+            object_layer
+                .objects
+                .iter()
+                .map(move |object| (object_layer, object))
+        })
+    }
+
+    /// Returns the first object with the given name.
+    pub fn object_by_name(&self, name: &str) -> Option<&TiledObject> {
+        self.all_objects()
+            .map(|(_, object)| object)
+            .find(|object| object.name == name)
+    }
+
+    /// Returns the object with the given id, which Tiled keeps unique across the map.
+    pub fn object_by_id(&self, id: u32) -> Option<&TiledObject> {
+        self.all_objects()
+            .map(|(_, object)| object)
+            .find(|object| object.id == id)
+    }
+
+    /// The map as parsed by the `tiled` crate, with its tilesets sorted by first gid.
+    pub fn tiled(&self) -> &tiled::Map {
+        &self.map
+    }
+
+    /// Returns the texture atlas of the tileset at `index` in `tiled().tilesets`, or `None` if
+    /// there is no such tileset or it is an image collection.
+    ///
+    /// The atlas is added to `Assets<TextureAtlas>` once the map has been spawned, and is shared
+    /// by every map entity drawing this map. A tile's index in the atlas is its local tile id.
+    pub fn atlas_for_tileset(&self, index: usize) -> Option<Handle<TextureAtlas>> {
+        let tileset = self.map.tilesets.get(index)?;
+        self.tileset_atlases.get(&tileset.first_gid).copied()
+    }
+
+    /// Returns the texture of the tileset image at `index` in `tiled().tilesets`, or `None` if
+    /// there is no such tileset, it is an image collection or it hasn't been spawned yet.
+    pub fn texture_for_tileset(
+        &self,
+        index: usize,
+        textures: &TiledTextures,
+    ) -> Option<Handle<Texture>> {
+        let tileset = self.map.tilesets.get(index)?;
+        if let Some(image) = self.tileset_embedded_images.get(&tileset.first_gid) {
+            return Some(image.handle);
+        }
+        let path = self.tileset_image_paths.get(&tileset.first_gid)?;
+        textures.handles.get(path).copied()
+    }
+
+    /// Returns the tileset the given gid belongs to, which is the one with the largest first gid
+    /// that is not above it. The gid must not include flip flags.
+    ///
+    /// Returns `None` for gids past the end of their tileset, such as tiles left behind by a
+    /// tileset removed in Tiled.
+    pub fn tileset_by_gid(&self, gid: u32) -> Option<&tiled::Tileset> {
+        self.tileset_and_local_id(gid)
+            .map(|(index, _)| &self.map.tilesets[index])
+    }
+
+    /// Returns the index in `map.tilesets` of the tileset a gid belongs to, and the tile's local
+    /// id within it. Flip flags are stripped from the gid first.
+    ///
+    /// Like `tileset_by_gid`, returns `None` for gids that aren't in any tileset.
+    pub fn tileset_and_local_id(&self, gid: u32) -> Option<(usize, u32)> {
+        let gid = gid & !ALL_FLIP_FLAGS;
+        if gid == 0 {
+            return None;
+        }
+        // The loader sorts tilesets by first gid.
+        let index = self
+            .map
+            .tilesets
+            .iter()
+            .rposition(|tileset| tileset.first_gid <= gid)?;
+        let tileset = &self.map.tilesets[index];
+        let local_id = gid - tileset.first_gid;
+        let has_tile = match tileset.images.first() {
+            Some(image) => {
+                local_id
+                    < tileset.tilecount.unwrap_or_else(|| {
+                        tileset_columns(tileset, image) * tileset_rows(tileset, image)
+                    })
+            }
+            // Image collections list each of their tiles, and their ids may have gaps.
+            None => tileset.tiles.iter().any(|tile| tile.id == local_id),
+        };
+        if has_tile {
+            Some((index, local_id))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the probability Tiled's random brush picks the tile with the given gid with,
+    /// relative to the other tiles. Tiles without a probability default to 1.0 like in Tiled.
+    pub fn tile_probability(&self, gid: u32) -> f32 {
+        self.tile_probabilities.get(&gid).copied().unwrap_or(1.0)
+    }
+
+    /// Returns the class of the tile with the given gid, if it has one.
+    pub fn tile_class(&self, gid: u32) -> Option<&str> {
+        self.tile_classes.get(&gid).map(String::as_str)
+    }
+
+    /// Returns the collision shapes of the tile with the given gid.
+    pub fn tile_collision(&self, gid: u32) -> &[TiledObject] {
+        self.tile_collisions
+            .get(&gid)
+            .map(|objects| objects.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Returns the gid at `x`, `y` of a tile layer, or 0 if the cell is empty.
+    ///
+    /// `layer_index` indexes the map's tile layers in `map.layers`, which includes hidden layers.
+    /// Returns `None` if there is no such layer or the position is outside the map.
+    pub fn get_tile(&self, layer_index: usize, x: u32, y: u32) -> Option<u32> {
+        self.tile_grid(layer_index)?.get(x, y)
+    }
+
+    /// Returns a 2d view of a tile layer's gids, with `layer_index` interpreted like in
+    /// `get_tile`.
+    pub fn tile_grid(&self, layer_index: usize) -> Option<TileGrid<'_>> {
+        match &self.map.layers.get(layer_index)?.tiles {
+            tiled::LayerData::Finite(rows) => {
+                Some(TileGrid::new(rows, self.map.width, self.map.height))
+            }
+            tiled::LayerData::Infinite(_) => None,
+        }
+    }
+
+    /// Iterates the neighbors of the cell at `x`, `y` of a tile layer as `((x, y), gid)`, skipping
+    /// cells outside of the map. `layer_index` is interpreted like in `get_tile`.
+    ///
+    /// Neighbors follow the map's orientation. Hexagons always have the 6 cells around them as
+    /// neighbors. The diamonds of staggered maps share their edges with the cells in the rows or
+    /// columns next to them, and their corners with the cells two rows or columns away.
+    pub fn neighbors(
+        &self,
+        layer_index: usize,
+        x: u32,
+        y: u32,
+        neighborhood: TileNeighborhood,
+    ) -> impl Iterator<Item = ((u32, u32), u32)> + '_ {
+        let layout = self.hex_layout;
+        let axis = match self.map.orientation {
+            tiled::Orientation::Hexagonal | tiled::Orientation::Staggered => layout.stagger_axis,
+            _ => StaggerAxis::Y,
+        };
+        // Offsets are written for a `Y` stagger axis and swapped for `X`.
+        let (along, across) = match axis {
+            StaggerAxis::X => (y as i64, x as i64),
+            StaggerAxis::Y => (x as i64, y as i64),
+        };
+        // The cells in the rows next to a shifted row are half a tile further to the right.
+        let shift = if layout.is_staggered(across as f32) {
+            0
+        } else {
+            -1
+        };
+        let hex_offsets = [
+            (-1, 0),
+            (1, 0),
+            (shift, -1),
+            (shift + 1, -1),
+            (shift, 1),
+            (shift + 1, 1),
+        ];
+        let offsets: Vec<(i64, i64)> = match self.map.orientation {
+            tiled::Orientation::Hexagonal => hex_offsets.to_vec(),
+            tiled::Orientation::Staggered => {
+                let mut offsets = hex_offsets[2..].to_vec();
+                if neighborhood == TileNeighborhood::Eight {
+                    offsets.extend_from_slice(&[(-1, 0), (1, 0), (0, -2), (0, 2)]);
+                }
+                offsets
+            }
+            _ => {
+                let mut offsets = vec![(0, -1), (1, 0), (0, 1), (-1, 0)];
+                if neighborhood == TileNeighborhood::Eight {
+                    offsets.extend_from_slice(&[(1, -1), (1, 1), (-1, 1), (-1, -1)]);
+                }
+                offsets
+            }
+        };
+        offsets.into_iter().filter_map(move |(d_along, d_across)| {
+            let (along, across) = (along + d_along, across + d_across);
+            let (x, y) = match axis {
+                StaggerAxis::X => (across, along),
+                StaggerAxis::Y => (along, across),
+            };
+            if x < 0 || y < 0 {
+                return None;
+            }
+            let (x, y) = (x as u32, y as u32);
+            self.get_tile(layer_index, x, y).map(|gid| ((x, y), gid))
+        })
+    }
+
+    /// Returns rectangles covering the non-empty tiles of the tile layer named `layer_name`, or
+    /// no rectangles if there is no such layer.
+    ///
+    /// Without `merge`, or on maps that aren't orthogonal, every tile gets its own rectangle.
+    /// Otherwise neighboring tiles are merged into as few rectangles as the greedy search finds.
+    pub fn collider_rects(&self, layer_name: &str, merge: bool) -> Vec<TileRect> {
+        let grid = match self
+            .layer_index_by_name(layer_name)
+            .and_then(|index| self.tile_grid(index))
+        {
+            Some(grid) => grid,
+            None => return Vec::new(),
+        };
+        if merge && self.map.orientation == tiled::Orientation::Orthogonal {
+            return merge_tile_rects(grid);
         }
+        (0..grid.height())
+            .flat_map(|y| (0..grid.width()).map(move |x| (x, y)))
+            .filter(|&(x, y)| grid.get(x, y).is_some_and(|gid| gid != 0))
+            .map(|(x, y)| TileRect {
+                x,
+                y,
+                width: 1,
+                height: 1,
+            })
+            .collect()
+    }
+
+    /// Returns the center, relative to the map's transform, and the size of the box covering a
+    /// rectangle of cells. Rectangles of more than one tile are only boxes on orthogonal maps.
+    pub fn tile_rect_to_world(&self, rect: TileRect) -> (Vec2, Vec2) {
+        let first = self.tile_to_world(rect.x, rect.y);
+        let last = self.tile_to_world(
+            rect.x + rect.width.max(1) - 1,
+            rect.y + rect.height.max(1) - 1,
+        );
+        let size = Vec2::new(rect.width as f32, rect.height as f32) * self.tile_size;
+        ((first + last) / 2.0, size)
+    }
+
+    /// Like `get_tile`, but looks up the first tile layer with the given name.
+    pub fn get_tile_by_layer_name(&self, layer_name: &str, x: u32, y: u32) -> Option<u32> {
+        self.get_tile(self.layer_index_by_name(layer_name)?, x, y)
+    }
+
+    /// Returns the index of the first tile layer with the given name, for use with `get_tile`
+    /// and `iter_layer_tiles`.
+    pub fn layer_index_by_name(&self, name: &str) -> Option<usize> {
+        self.map.layers.iter().position(|layer| layer.name == name)
+    }
+
+    /// Returns the index of the tile layer with the given id, which stays the same when the
+    /// layer is renamed or moved in Tiled.
+    pub fn layer_index_by_id(&self, id: u32) -> Option<usize> {
+        self.layers.iter().position(|layer| layer.id == Some(id))
+    }
+
+    /// Returns the first tile layer in `layers` with the given name.
+    pub fn layer_by_name(&self, name: &str) -> Option<&Layer> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
+    /// Like `layer_by_name`, but returns the layer mutably.
+    pub fn layer_by_name_mut(&mut self, name: &str) -> Option<&mut Layer> {
+        self.layers.iter_mut().find(|layer| layer.name == name)
+    }
+
+    /// Iterates the non-empty cells of a tile layer as `(x, y, gid)`, row by row.
+    ///
+    /// `layer_index` is interpreted like in `get_tile`. Yields nothing if there is no such layer.
+    pub fn iter_layer_tiles(
+        &self,
+        layer_index: usize,
+    ) -> impl Iterator<Item = (u32, u32, u32)> + '_ {
+        let rows = match self.map.layers.get(layer_index).map(|layer| &layer.tiles) {
+            Some(tiled::LayerData::Finite(rows)) => rows.as_slice(),
+            _ => &[],
+        };
+        rows.iter().enumerate().flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, tile)| tile.gid != 0)
+                .map(move |(x, tile)| (x as u32, y as u32, tile.gid))
+        })
+    }
+
+    /// Returns how many columns of tiles the image of the tileset starting at `first_gid` has,
+    /// accounting for its margin and spacing.
+    ///
+    /// Returns `None` if there is no such tileset or it is an image collection. The image's path
+    /// is in `tileset_image_paths` and its size in pixels on the tileset's `images`.
+    pub fn tileset_columns(&self, first_gid: u32) -> Option<u32> {
+        let tileset = self.tileset_by_first_gid(first_gid)?;
+        Some(tileset_columns(tileset, tileset.images.first()?))
+    }
+
+    /// Like `tileset_columns`, but returns how many rows of tiles the image has.
+    pub fn tileset_rows(&self, first_gid: u32) -> Option<u32> {
+        let tileset = self.tileset_by_first_gid(first_gid)?;
+        Some(tileset_rows(tileset, tileset.images.first()?))
+    }
+
+    fn tileset_by_first_gid(&self, first_gid: u32) -> Option<&tiled::Tileset> {
+        self.map
+            .tilesets
+            .iter()
+            .find(|tileset| tileset.first_gid == first_gid)
+    }
+
+    /// Splits the image of the tileset starting at `first_gid` into a texture atlas, so tiles can
+    /// be drawn as sprites. A tile's index in the atlas is its local tile id.
+    ///
+    /// Returns `None` if there is no such tileset or it is an image collection.
+    pub fn tileset_texture_atlas(
+        &self,
+        first_gid: u32,
+        texture: Handle<Texture>,
+    ) -> Option<TextureAtlas> {
+        let tileset = self.tileset_by_first_gid(first_gid)?;
+        let image = tileset.images.first()?;
+        let columns = tileset_columns(tileset, image);
+        let tile_count = tileset
+            .tilecount
+            .unwrap_or_else(|| columns * tileset_rows(tileset, image));
+
+        let tile_size = Vec2::new(tileset.tile_width as f32, tileset.tile_height as f32);
+        let mut texture_atlas =
+            TextureAtlas::new_empty(texture, Vec2::new(image.width as f32, image.height as f32));
+        for tile in 0..tile_count {
+            let column = (tile % columns.max(1)) as f32;
+            let row = (tile / columns.max(1)) as f32;
+            let min = Vec2::new(
+                tileset.margin as f32 + column * (tile_size.x() + tileset.spacing as f32),
+                tileset.margin as f32 + row * (tile_size.y() + tileset.spacing as f32),
+            );
+            texture_atlas.add_texture(bevy::sprite::Rect {
+                min,
+                max: min + tile_size,
+            });
+        }
+        Some(texture_atlas)
+    }
+
+    /// Returns the z the renderer places the named layer at, relative to the map's transform.
+    ///
+    /// Each tile layer sits one unit above the one before it, including hidden layers.
+    pub fn layer_z(&self, name: &str) -> Option<f32> {
+        self.layers
+            .iter()
+            .position(|layer| layer.name == name)
+            .map(|layer_id| layer_id as f32)
+    }
+
+    /// Writes the map as a TMX document, for saving a map edited at runtime.
+    ///
+    /// The tiles of each tile layer are written from the `tiled` map's `layers`, encoded as
+    /// base64 compressed with zlib. Everything else is written as it was loaded, including
+    /// external tileset references, so JSON maps are converted to TMX. Paths stay relative to
+    /// the map's file, so save the document next to it.
+    pub fn to_tmx_string(&self) -> Result<String> {
+        let mut document = self.document.clone();
+        let mut layers = self.map.layers.iter();
+        encode_tile_layers(&mut document, &mut layers)?;
+        Ok(String::from_utf8(document.to_bytes()?)?)
+    }
+
+    /// Builds the chunk meshes for this map.
+    ///
+    /// Tiles within a mesh are drawn in the map's render order. Separate chunks don't overlap
+    /// in a defined order.
+    ///
+    /// Merged layers share one mesh per chunk and tileset, with each layer's depth baked into
+    /// the vertex z relative to the first merged layer. Only layers with the same color and
+    /// parallax factors are merged together.
+    pub fn build_meshes(&self, merge: &TiledMapMergeLayers) -> Vec<ChunkMesh> {
+        self.build_meshes_for(merge, None, None)
+    }
+
+    /// Builds the meshes a tile layer is drawn with, which also draw the layers merged with it.
+    /// All of them have the layer's `mesh_layer_id` as their `layer_id`.
+    pub fn build_layer_meshes(
+        &self,
+        merge: &TiledMapMergeLayers,
+        layer_index: usize,
+    ) -> Vec<ChunkMesh> {
+        self.build_meshes_for(merge, Some(self.mesh_layer_id(merge, layer_index)), None)
+    }
+
+    /// Builds the meshes of one chunk of a tile layer, like `build_layer_meshes`.
+    pub fn build_chunk_meshes(
+        &self,
+        merge: &TiledMapMergeLayers,
+        layer_index: usize,
+        position: TiledChunkPosition,
+    ) -> Vec<ChunkMesh> {
+        self.build_meshes_for(
+            merge,
+            Some(self.mesh_layer_id(merge, layer_index)),
+            Some(position),
+        )
+    }
+
+    /// Returns the `layer_id` of the meshes a tile layer is drawn with: its own index, or the
+    /// index of the first layer it is merged with.
+    pub fn mesh_layer_id(&self, merge: &TiledMapMergeLayers, layer_index: usize) -> usize {
+        self.merge_groups(merge)
+            .into_iter()
+            .find(|layer_ids| layer_ids.contains(&layer_index))
+            .map_or(layer_index, |layer_ids| layer_ids[0])
+    }
+
+    /// Lays the gids of a tile layer out into its `tileset_layers` again, after they were edited
+    /// in `map.layers`.
+    pub fn rebuild_layer(&mut self, layer_index: usize) -> Result<()> {
+        let layer = match (
+            self.layers.get(layer_index),
+            self.map.layers.get(layer_index),
+        ) {
+            (Some(layer), Some(tiled_layer)) => {
+                // Layer offsets point down like in Tiled, tiles are placed with y pointing up.
+                let offset = Vec2::new(layer.offset_x, -layer.offset_y);
+                build_tileset_layers(
+                    &self.map,
+                    tiled_layer,
+                    &tileset_textures(&self.map.tilesets),
+                    &self.tile_offsets,
+                    offset,
+                    self.hex_layout,
+                )?
+            }
+            _ => bail!("the map has no tile layer {}", layer_index),
+        };
+        self.layers[layer_index].tileset_layers = layer;
+        Ok(())
+    }
+
+    /// Changes the tile at `x`, `y` of a tile layer to `gid`, which has Tiled's flip flags in the
+    /// top bits and is 0 to clear the cell.
+    ///
+    /// Only the chunk holding the tile is laid out again. Change tiles of a spawned map with
+    /// `set_map_tile` to only respawn what draws the tile.
+    pub fn set_tile(&mut self, layer_index: usize, x: u32, y: u32, gid: u32) -> Result<()> {
+        if x >= self.map.width || y >= self.map.height {
+            bail!("tile ({}, {}) is outside of the map", x, y);
+        }
+        if gid & !ALL_FLIP_FLAGS != 0 && self.tileset_and_local_id(gid).is_none() {
+            bail!(
+                "gid {} isn't in any of the map's tilesets",
+                gid & !ALL_FLIP_FLAGS
+            );
+        }
+        let tiles = match self
+            .map
+            .layers
+            .get_mut(layer_index)
+            .map(|layer| &mut layer.tiles)
+        {
+            Some(tiled::LayerData::Finite(tiles)) if layer_index < self.layers.len() => tiles,
+            _ => bail!("the map has no tile layer {}", layer_index),
+        };
+        let tile = &mut tiles[y as usize][x as usize];
+        let previous_gid = tile.gid
+            | if tile.flip_h {
+                FLIPPED_HORIZONTALLY_FLAG
+            } else {
+                0
+            }
+            | if tile.flip_v {
+                FLIPPED_VERTICALLY_FLAG
+            } else {
+                0
+            }
+            | if tile.flip_d {
+                FLIPPED_DIAGONALLY_FLAG
+            } else {
+                0
+            };
+        *tile = tiled::LayerTile::new(gid);
+
+        let position = TiledChunkPosition {
+            x: x as usize / CHUNK_TILES,
+            y: y as usize / CHUNK_TILES,
+        };
+        let layer = &self.layers[layer_index];
+        // Layer offsets point down like in Tiled, tiles are placed with y pointing up.
+        let chunks = build_tileset_chunks(
+            &self.map,
+            &self.map.layers[layer_index],
+            &tileset_textures(&self.map.tilesets),
+            &self.tile_offsets,
+            Vec2::new(layer.offset_x, -layer.offset_y),
+            self.hex_layout,
+            (position.x, position.y),
+        )?;
+        for (tileset_layer, chunk) in self.layers[layer_index]
+            .tileset_layers
+            .iter_mut()
+            .zip(chunks)
+        {
+            tileset_layer.chunks[position.x][position.y] = chunk;
+        }
+        self.changed_tiles.push(TileChange {
+            layer_index,
+            x,
+            y,
+            gid,
+            previous_gid,
+        });
+        Ok(())
+    }
+
+    /// Hidden layers keep their own meshes, so they can be shown on their own.
+    fn is_merged(merge: &TiledMapMergeLayers, layer: &Layer) -> bool {
+        merge.enabled && layer.visible && !merge.excluded.contains(&layer.name)
+    }
+
+    /// Returns the indices of the layers merged into the same meshes, for layers that can share
+    /// a color and parallax factor.
+    fn merge_groups(&self, merge: &TiledMapMergeLayers) -> Vec<Vec<usize>> {
+        let merge_key = |layer: &Layer| (layer.color(), layer.parallax_x, layer.parallax_y);
+        let mut merge_groups: Vec<((Color, f32, f32), Vec<usize>)> = Vec::new();
+        for (layer_id, layer) in self.layers.iter().enumerate() {
+            if !Map::is_merged(merge, layer) {
+                continue;
+            }
+            match merge_groups
+                .iter_mut()
+                .find(|(key, _)| *key == merge_key(layer))
+            {
+                Some((_, layer_ids)) => layer_ids.push(layer_id),
+                None => merge_groups.push((merge_key(layer), vec![layer_id])),
+            }
+        }
+        merge_groups
+            .into_iter()
+            .map(|(_, layer_ids)| layer_ids)
+            .collect()
+    }
+
+    /// Builds the meshes of all layers, or only those with the given `layer_id`.
+    fn build_meshes_for(
+        &self,
+        merge: &TiledMapMergeLayers,
+        mesh_layer_id: Option<usize>,
+        chunk_position: Option<TiledChunkPosition>,
+    ) -> Vec<ChunkMesh> {
+        let is_included = |layer_id: usize| mesh_layer_id.is_none_or(|id| id == layer_id);
+        let is_chunk_included = |position: TiledChunkPosition| {
+            chunk_position.is_none_or(|chunk_position| chunk_position == position)
+        };
+
+        let mut meshes = Vec::new();
+        for (layer_id, layer) in self.layers.iter().enumerate() {
+            if Map::is_merged(merge, layer) || !is_included(layer_id) {
+                continue;
+            }
+            for tileset_layer in layer.tileset_layers.iter() {
+                for (chunk_x, chunks_y) in tileset_layer.chunks.iter().enumerate() {
+                    for (chunk_y, chunk) in chunks_y.iter().enumerate() {
+                        let position = TiledChunkPosition {
+                            x: chunk_x,
+                            y: chunk_y,
+                        };
+                        if !is_chunk_included(position) {
+                            continue;
+                        }
+                        let mut builder = ChunkMeshBuilder::default();
+                        builder.add_chunk(self, chunk, tileset_layer.tileset_guid, 0.0);
+                        for (mesh, animated_tiles, bounds) in builder.finish() {
+                            meshes.push(ChunkMesh {
+                                layer_id: layer_id as u32,
+                                position,
+                                tileset_guid: tileset_layer.tileset_guid,
+                                mesh,
+                                animated_tiles,
+                                bounds,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        for layer_ids in self.merge_groups(merge) {
+            let base_layer_id = layer_ids[0];
+            if !is_included(base_layer_id) {
+                continue;
+            }
+            let base_layer = &self.layers[base_layer_id];
+            for (tileset_index, tileset_layer) in base_layer.tileset_layers.iter().enumerate() {
+                for (chunk_x, chunks_y) in tileset_layer.chunks.iter().enumerate() {
+                    for chunk_y in 0..chunks_y.len() {
+                        let position = TiledChunkPosition {
+                            x: chunk_x,
+                            y: chunk_y,
+                        };
+                        if !is_chunk_included(position) {
+                            continue;
+                        }
+                        let mut builder = ChunkMeshBuilder::default();
+                        for layer_id in layer_ids.iter() {
+                            let layer_tileset =
+                                &self.layers[*layer_id].tileset_layers[tileset_index];
+                            builder.add_chunk(
+                                self,
+                                &layer_tileset.chunks[chunk_x][chunk_y],
+                                layer_tileset.tileset_guid,
+                                (layer_id - base_layer_id) as f32,
+                            );
+                        }
+                        for (mesh, animated_tiles, bounds) in builder.finish() {
+                            meshes.push(ChunkMesh {
+                                layer_id: base_layer_id as u32,
+                                position,
+                                tileset_guid: tileset_layer.tileset_guid,
+                                mesh,
+                                animated_tiles,
+                                bounds,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        meshes
     }
 }
 
 #[derive(Default)]
 pub struct TiledMapCenter(pub bool);
 
+/// The point of the map placed at the map entity's `origin`, unless the map is centered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TiledMapAnchor {
+    /// The top left corner of the map, where Tiled measures pixel positions from, so objects and
+    /// tiles are at the same positions as in the editor with y flipped. On isometric maps this
+    /// is the top corner of the first tile.
+    ///
+    /// Tile objects are still anchored at the bottom left of their image, like in Tiled.
+    #[default]
+    TopLeft,
+    /// The center of the first tile, at the top left of the map.
+    FirstTileCenter,
+}
+
+/// Sets the `ClearColor` to the map's background color when the map is spawned.
+#[derive(Default)]
+pub struct TiledMapClearColor(pub bool);
+
+/// Merges the map's tile layers into shared chunk meshes when enabled.
+///
+/// Layers named in `excluded` keep their own meshes so they can still be addressed on their own.
+///
+/// The color and opacity of a chunk, and the parallax of its entity, are shared by all of its
+/// tiles. Only layers with the same color, opacity and parallax factors are merged, so a map
+/// whose layers differ in those still spawns a mesh for each group of matching layers.
+#[derive(Default)]
+pub struct TiledMapMergeLayers {
+    pub enabled: bool,
+    pub excluded: Vec<String>,
+}
+
+/// Spawns the chunks of hidden tile layers when enabled, so they can be shown later through
+/// their `TiledLayerVisibility`. Otherwise hidden layers, such as editor reference layers, don't
+/// spawn any entities.
+#[derive(Default)]
+pub struct TiledMapSpawnInvisibleLayers(pub bool);
+
+/// How a map's tile layers are drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TileRenderMode {
+    /// Tiles are batched into chunk meshes drawn by the tile map pipeline, with one entity per
+    /// chunk, layer and tileset.
+    #[default]
+    Mesh,
+    /// Every tile is spawned as a sprite entity with a `TiledTileSprite` component.
+    ///
+    /// This is slower for large maps, and tiles aren't animated or culled. Tiles from image
+    /// collection tilesets aren't tinted by their layer's color.
+    Sprites,
+}
+
+/// Marks a tile sprite spawned with `TileRenderMode::Sprites`.
+#[derive(Debug, Clone)]
+pub struct TiledTileSprite {
+    /// The index of the tile's layer in `Map::layers`.
+    pub layer_index: usize,
+    /// The tile's cell in the layer.
+    pub x: u32,
+    pub y: u32,
+}
+
+/// The chunk of its layer a chunk entity draws, counted in chunks of 32 by 32 tiles from the
+/// top left of the map.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TiledChunkPosition {
+    pub x: usize,
+    pub y: usize,
+}
+
+/// The flips Tiled stores in a tile's gid, on tile sprites spawned with
+/// `TileRenderMode::Sprites`. They are already applied to the sprite's transform.
+///
+/// The diagonal flip is applied first, so together the flags give all eight orientations:
+///
+/// | `h` | `v` | `d` | Tile                                                  |
+/// |-----|-----|-----|-------------------------------------------------------|
+/// |     |     |     | unchanged                                             |
+/// | x   |     |     | mirrored horizontally                                 |
+/// |     | x   |     | mirrored vertically                                   |
+/// | x   | x   |     | rotated by 180°                                       |
+/// |     |     | x   | mirrored across its top left to bottom right diagonal |
+/// | x   |     | x   | rotated 90° clockwise                                 |
+/// |     | x   | x   | rotated 90° counterclockwise                          |
+/// | x   | x   | x   | mirrored across its top right to bottom left diagonal |
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TileFlip {
+    pub h: bool,
+    pub v: bool,
+    pub d: bool,
+}
+
+impl TileFlip {
+    /// Returns the transform that flips a sprite centered on the origin like the tile.
+    pub fn transform(&self) -> Mat4 {
+        flip_transform(self.h, self.v, self.d)
+    }
+}
+
+/// Respawns the tile layer at `layer_index` in `Map::layers` of a map entity from the map's
+/// current data in the next update, without respawning the rest of the map.
+///
+/// Edit the layer through `map_mut_without_event`, calling `Map::rebuild_layer` if its gids
+/// were changed. Editing it through `Assets::get_mut` sends the map's `Modified` event instead,
+/// which respawns the whole map anyway.
+///
+/// Like when the map is spawned, the layer is shown or hidden following `Layer::visible`, and
+/// layers merged into the same meshes are respawned together. To change single tiles,
+/// `set_map_tile` only respawns the chunks or sprites drawing them.
+pub fn refresh_layer(commands: &mut Commands, map_entity: Entity, layer_index: usize) {
+    commands.write_resources(RefreshLayer {
+        map_entity,
+        layer_index,
+    });
+}
+
+struct RefreshLayer {
+    map_entity: Entity,
+    layer_index: usize,
+}
+
+impl ResourcesWriter for RefreshLayer {
+    fn write(self: Box<Self>, resources: &mut Resources) {
+        if let Some(mut refreshes) = resources.get_mut::<TiledLayerRefreshes>() {
+            refreshes.0.push((self.map_entity, self.layer_index));
+        }
+    }
+}
+
+/// The tile layers passed to `refresh_layer`, as a map entity and an index in `Map::layers`,
+/// waiting to be respawned.
+#[derive(Debug, Default)]
+pub struct TiledLayerRefreshes(Vec<(Entity, usize)>);
+
+/// A tile changed with `Map::set_tile`, waiting to be respawned.
+#[derive(Debug, Clone)]
+pub(crate) struct TileChange {
+    layer_index: usize,
+    x: u32,
+    y: u32,
+    gid: u32,
+    previous_gid: u32,
+}
+
+/// Sent when a tile of a map was changed with `Map::set_tile` or `set_map_tile`, once the
+/// chunks or sprites drawing it have been respawned.
+#[derive(Debug, Clone)]
+pub struct TileChanged {
+    pub map: Handle<Map>,
+    /// The index of the tile's layer in `Map::layers`.
+    pub layer_index: usize,
+    pub x: u32,
+    pub y: u32,
+    /// The tile's new gid, with Tiled's flip flags in the top bits and 0 for an empty cell.
+    pub gid: u32,
+    pub previous_gid: u32,
+}
+
+/// Changes a tile of a loaded map like `Map::set_tile`, respawning only the chunk or sprite
+/// drawing it in each map entity showing the map.
+pub fn set_map_tile(
+    maps: &mut Assets<Map>,
+    map: &Handle<Map>,
+    layer_index: usize,
+    x: u32,
+    y: u32,
+    gid: u32,
+) -> Result<()> {
+    match map_mut_without_event(maps, *map) {
+        Some(map) => map.set_tile(layer_index, x, y, gid),
+        None => bail!("the map isn't loaded"),
+    }
+}
+
+/// Returns a loaded map to edit without sending its `Modified` event, or `None` if it isn't
+/// loaded.
+///
+/// `Assets::get_mut` always sends `Modified`, which despawns and respawns everything spawned
+/// for the map, while tile edits only respawn the chunks or sprites they touch.
+///
+/// This depends on bevy 0.2's `Assets` internals rather than a documented guarantee: its
+/// `get_or_insert_with` only sends an event when it inserts, which can't happen once the map is
+/// known to be loaded. Check it again when upgrading bevy, as tile edits respawn the whole map
+/// if it ever sends `Modified`.
+pub fn map_mut_without_event(maps: &mut Assets<Map>, handle: Handle<Map>) -> Option<&mut Map> {
+    maps.get(&handle)?;
+    Some(maps.get_or_insert_with(handle, || unreachable!("the map is loaded")))
+}
+
+/// The chunk and object entities spawned for a map entity, which are despawned and spawned again
+/// when the map asset is modified.
+#[derive(Default)]
+pub struct TiledMapEntities(pub Vec<Entity>);
+
+/// Marks an entity spawned for the map with the given handle.
+///
+/// Several map entities can show the same map. Each spawns its own entities, told apart by
+/// `entity`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TiledMapEntity {
+    pub map: Handle<Map>,
+    /// The map entity it was spawned for.
+    pub entity: Entity,
+}
+
+/// Send this event to despawn every map entity with the given handle, along with the entities
+/// spawned for them.
+pub struct DespawnTiledMap {
+    pub map: Handle<Map>,
+}
+
+/// Sent once the chunks, objects and image layers of a map entity have been spawned, including
+/// after the map is reloaded.
+///
+/// The entities are spawned through `Commands`, so they exist for systems in later stages and
+/// from the next frame on.
+pub struct MapSpawned {
+    pub map: Handle<Map>,
+    /// The map entity they were spawned for.
+    pub entity: Entity,
+}
+
+/// The textures of tileset and image layer images, keyed by resolved path.
+///
+/// Maps load each image through this resource, so an image shared by several tilesets or maps is
+/// loaded once and they all draw with the same `Handle<Texture>`.
+#[derive(Default)]
+pub struct TiledTextures {
+    pub handles: HashMap<PathBuf, Handle<Texture>>,
+    /// Drawn instead of images that are missing, when `TiledMapPlugin` is set up with
+    /// `placeholder_on_missing_image`.
+    pub placeholder: Option<Handle<Texture>>,
+    /// The images that failed to load, which are loaded again when a map is reloaded.
+    pub missing: HashSet<PathBuf>,
+}
+
+impl TiledTextures {
+    /// Returns the texture of the image at `path`, starting to load it the first time.
+    ///
+    /// An image the asset server has no loader for is logged and drawn with the placeholder, or
+    /// left blank without one. Images that fail to load are drawn with the placeholder by
+    /// `draw_missing_images`.
+    pub fn load(&mut self, asset_server: &AssetServer, path: &Path) -> Handle<Texture> {
+        if let Some(handle) = self.handles.get(path) {
+            return *handle;
+        }
+        let handle = match asset_server.load(path) {
+            Ok(handle) => handle,
+            Err(error) => {
+                warn!("failed to load image {:?}: {}", path, error);
+                self.missing.insert(path.to_path_buf());
+                self.placeholder.unwrap_or_default()
+            }
+        };
+        self.handles.insert(path.to_path_buf(), handle);
+        handle
+    }
+
+    /// Forgets the images that failed to load, so the next `load` of each tries again.
+    pub fn forget_missing(&mut self) {
+        for path in self.missing.drain() {
+            self.handles.remove(&path);
+        }
+    }
+}
+
+/// Draws images whose file failed to load with the placeholder of `TiledTextures`, logging a
+/// warning for each.
+pub fn draw_missing_images(
+    asset_server: Res<AssetServer>,
+    mut tiled_textures: ResMut<TiledTextures>,
+    mut textures: ResMut<Assets<Texture>>,
+) {
+    let placeholder = match tiled_textures
+        .placeholder
+        .and_then(|placeholder| textures.get(&placeholder).cloned())
+    {
+        Some(placeholder) => placeholder,
+        None => return,
+    };
+    let tiled_textures = &mut *tiled_textures;
+    for (path, handle) in tiled_textures.handles.iter() {
+        if tiled_textures.missing.contains(path) {
+            continue;
+        }
+        if let Some(LoadState::Failed(_)) = asset_server.get_load_state(*handle) {
+            warn!("image {:?} is missing, drawing a placeholder instead", path);
+            textures.set(*handle, placeholder.clone());
+            tiled_textures.missing.insert(path.clone());
+        }
+    }
+}
+
 /// A bundle of tiled map entities.
-#[derive(Bundle)]
+///
+/// Spawn this to place a map in the world. Its chunks, objects and image layers are spawned once
+/// the map asset has loaded, positioned relative to `origin`.
+#[derive(Bundle, Default)]
 pub struct TiledMapComponents {
     pub map_asset: Handle<Map>,
     pub materials: HashMap<u32, Handle<ColorMaterial>>,
+    /// A texture atlas of each tileset's image, keyed by first gid. These are the map's
+    /// `Map::tileset_atlases`, filled in once it is spawned.
+    pub texture_atlases: HashMap<u32, Handle<TextureAtlas>>,
     pub origin: Transform,
+    pub global_transform: GlobalTransform,
     pub center: TiledMapCenter,
+    pub anchor: TiledMapAnchor,
+    pub clear_color: TiledMapClearColor,
+    pub merge_tile_layers: TiledMapMergeLayers,
+    pub render_mode: TileRenderMode,
+    pub spawn_invisible_layers: TiledMapSpawnInvisibleLayers,
+    pub colliders: TiledMapColliders,
+    pub entities: TiledMapEntities,
 }
 
-impl Default for TiledMapComponents {
-    fn default() -> Self {
-        Self {
-            map_asset: Handle::default(),
-            materials: HashMap::default(),
-            center: TiledMapCenter::default(),
-            origin: Transform::default(),
+/// Another name for `TiledMapComponents`, for those used to the `Bundle` suffix.
+pub type TiledMapBundle = TiledMapComponents;
+
+/// An entity for an object from one of the map's object layers, placed at the object's position.
+#[derive(Bundle)]
+pub struct ObjectComponents {
+    pub object: TiledObject,
+    pub map_entity: TiledMapEntity,
+    pub transform: Transform,
+    pub global_transform: GlobalTransform,
+}
+
+#[derive(Default)]
+pub struct DespawnTiledMapState {
+    event_reader: EventReader<DespawnTiledMap>,
+}
+
+pub fn despawn_tiled_maps(
+    mut commands: Commands,
+    mut state: Local<DespawnTiledMapState>,
+    events: Res<Events<DespawnTiledMap>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut maps: Query<(Entity, &Handle<Map>)>,
+    mut spawned: Query<(Entity, &TiledMapEntity)>,
+    mesh_handles: Query<&Handle<Mesh>>,
+) {
+    for event in state.event_reader.iter(&events) {
+        for (entity, map_handle) in &mut maps.iter() {
+            if *map_handle == event.map {
+                commands.despawn_recursive(entity);
+            }
+        }
+        for (entity, map_entity) in &mut spawned.iter() {
+            if map_entity.map == event.map {
+                if let Ok(mesh_handle) = mesh_handles.get::<Handle<Mesh>>(entity) {
+                    meshes.remove(&mesh_handle);
+                }
+                commands.despawn_recursive(entity);
+            }
+        }
+    }
+}
+
+/// Respawns the chunks or sprites drawing tiles changed with `Map::set_tile`, and sends a
+/// `TileChanged` event for each tile.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn respawn_changed_tiles(
+    mut commands: Commands,
+    mut maps: ResMut<Assets<Map>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut tile_changed_events: ResMut<Events<TileChanged>>,
+    mut query: Query<(
+        Entity,
+        &Handle<Map>,
+        &TiledMapCenter,
+        &TiledMapAnchor,
+        &TiledMapMergeLayers,
+        &TileRenderMode,
+        &TiledMapSpawnInvisibleLayers,
+        &HashMap<u32, Handle<ColorMaterial>>,
+        &HashMap<u32, Handle<TextureAtlas>>,
+        &Transform,
+        &mut TiledMapEntities,
+    )>,
+    chunks: Query<(&TileMapChunk, &TiledChunkPosition)>,
+    tile_sprites: Query<&TiledTileSprite>,
+    mesh_handles: Query<&Handle<Mesh>>,
+) {
+    let changed_maps = maps
+        .iter()
+        .filter(|(_, map)| !map.changed_tiles.is_empty())
+        .map(|(handle, _)| handle)
+        .collect::<Vec<_>>();
+    for handle in changed_maps {
+        let changes = match map_mut_without_event(&mut maps, handle) {
+            Some(map) => std::mem::take(&mut map.changed_tiles),
+            None => continue,
+        };
+        let map = match maps.get(&handle) {
+            Some(map) => map,
+            None => continue,
+        };
+
+        for (
+            map_entity,
+            map_handle,
+            center,
+            anchor,
+            merge_tile_layers,
+            render_mode,
+            spawn_invisible_layers,
+            materials_map,
+            atlases_map,
+            origin,
+            mut entities,
+        ) in &mut query.iter()
+        {
+            if *map_handle != handle {
+                continue;
+            }
+            let spawner = LayerSpawner {
+                map,
+                map_entity: TiledMapEntity {
+                    map: handle,
+                    entity: map_entity,
+                },
+                materials: materials_map,
+                atlases: atlases_map,
+                transform: tile_map_transform(map, center, *anchor, *origin),
+                spawn_invisible_layers: spawn_invisible_layers.0,
+            };
+            let mut spawned = Vec::new();
+            match render_mode {
+                TileRenderMode::Mesh => {
+                    // Layers merged into the same meshes are respawned together.
+                    let mut refreshed_chunks = changes
+                        .iter()
+                        .map(|change| {
+                            (
+                                map.mesh_layer_id(merge_tile_layers, change.layer_index),
+                                TiledChunkPosition {
+                                    x: change.x as usize / CHUNK_TILES,
+                                    y: change.y as usize / CHUNK_TILES,
+                                },
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    refreshed_chunks.sort_unstable();
+                    refreshed_chunks.dedup();
+                    entities.0.retain(|entity| {
+                        let chunk = match (
+                            chunks.get::<TileMapChunk>(*entity),
+                            chunks.get::<TiledChunkPosition>(*entity),
+                        ) {
+                            (Ok(chunk), Ok(position)) => (chunk.layer_id as usize, *position),
+                            _ => return true,
+                        };
+                        if !refreshed_chunks.contains(&chunk) {
+                            return true;
+                        }
+                        if let Ok(mesh_handle) = mesh_handles.get::<Handle<Mesh>>(*entity) {
+                            meshes.remove(&mesh_handle);
+                        }
+                        commands.despawn(*entity);
+                        false
+                    });
+                    for (mesh_layer_id, position) in refreshed_chunks {
+                        for chunk_mesh in
+                            map.build_chunk_meshes(merge_tile_layers, mesh_layer_id, position)
+                        {
+                            spawned.extend(spawner.spawn_chunk_mesh(
+                                &mut commands,
+                                &mut meshes,
+                                chunk_mesh,
+                            ));
+                        }
+                    }
+                }
+                TileRenderMode::Sprites => {
+                    let mut refreshed_tiles = changes
+                        .iter()
+                        .map(|change| (change.layer_index, change.x, change.y))
+                        .collect::<Vec<_>>();
+                    refreshed_tiles.sort_unstable();
+                    refreshed_tiles.dedup();
+                    entities.0.retain(|entity| {
+                        match tile_sprites.get::<TiledTileSprite>(*entity) {
+                            Ok(sprite)
+                                if refreshed_tiles.contains(&(
+                                    sprite.layer_index,
+                                    sprite.x,
+                                    sprite.y,
+                                )) =>
+                            {
+                                commands.despawn(*entity);
+                                false
+                            }
+                            _ => true,
+                        }
+                    });
+                    for (layer_index, x, y) in refreshed_tiles {
+                        spawner.spawn_layer_sprites(
+                            &mut commands,
+                            layer_index,
+                            Some((x, y)),
+                            &mut spawned,
+                        );
+                    }
+                }
+            }
+            entities.0.extend(spawned);
+        }
+
+        for change in changes {
+            tile_changed_events.send(TileChanged {
+                map: handle,
+                layer_index: change.layer_index,
+                x: change.x,
+                y: change.y,
+                gid: change.gid,
+                previous_gid: change.previous_gid,
+            });
+        }
+    }
+}
+
+/// Respawns the tile layers passed to `refresh_layer`.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn respawn_refreshed_layers(
+    mut commands: Commands,
+    mut refreshes: ResMut<TiledLayerRefreshes>,
+    maps: Res<Assets<Map>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut query: Query<(
+        &Handle<Map>,
+        &TiledMapCenter,
+        &TiledMapAnchor,
+        &TiledMapMergeLayers,
+        &TileRenderMode,
+        &TiledMapSpawnInvisibleLayers,
+        &HashMap<u32, Handle<ColorMaterial>>,
+        &HashMap<u32, Handle<TextureAtlas>>,
+        &Transform,
+        &mut TiledMapEntities,
+    )>,
+    mesh_handles: Query<&Handle<Mesh>>,
+    chunks: Query<&TileMapChunk>,
+    tile_sprites: Query<&TiledTileSprite>,
+) {
+    let mut refreshed_layers = HashMap::<Entity, Vec<usize>>::new();
+    for (map_entity, layer_index) in refreshes.0.drain(..) {
+        refreshed_layers
+            .entry(map_entity)
+            .or_default()
+            .push(layer_index);
+    }
+
+    for (map_entity, refreshed_layers) in refreshed_layers {
+        let mut map_entity_query = match query.entity(map_entity) {
+            Ok(map_entity_query) => map_entity_query,
+            Err(_) => continue,
+        };
+        let (
+            map_handle,
+            center,
+            anchor,
+            merge_tile_layers,
+            render_mode,
+            spawn_invisible_layers,
+            materials_map,
+            atlases_map,
+            origin,
+            mut entities,
+        ) = match map_entity_query.get() {
+            Some(components) => components,
+            None => continue,
+        };
+        let map = match maps.get(map_handle) {
+            Some(map) => map,
+            None => continue,
+        };
+        let spawner = LayerSpawner {
+            map,
+            map_entity: TiledMapEntity {
+                map: *map_handle,
+                entity: map_entity,
+            },
+            materials: materials_map,
+            atlases: atlases_map,
+            transform: tile_map_transform(map, center, *anchor, *origin),
+            spawn_invisible_layers: spawn_invisible_layers.0,
+        };
+        let mut spawned = Vec::new();
+        match render_mode {
+            TileRenderMode::Mesh => {
+                // Layers merged into the same meshes are respawned together.
+                let mut mesh_layer_ids = refreshed_layers
+                    .iter()
+                    .filter(|layer_index| **layer_index < map.layers.len())
+                    .map(|layer_index| map.mesh_layer_id(merge_tile_layers, *layer_index))
+                    .collect::<Vec<_>>();
+                mesh_layer_ids.sort_unstable();
+                mesh_layer_ids.dedup();
+                entities
+                    .0
+                    .retain(|entity| match chunks.get::<TileMapChunk>(*entity) {
+                        Ok(chunk) if mesh_layer_ids.contains(&(chunk.layer_id as usize)) => {
+                            if let Ok(mesh_handle) = mesh_handles.get::<Handle<Mesh>>(*entity) {
+                                meshes.remove(&mesh_handle);
+                            }
+                            commands.despawn(*entity);
+                            false
+                        }
+                        _ => true,
+                    });
+                for mesh_layer_id in mesh_layer_ids {
+                    for chunk_mesh in map.build_layer_meshes(merge_tile_layers, mesh_layer_id) {
+                        spawned.extend(spawner.spawn_chunk_mesh(
+                            &mut commands,
+                            &mut meshes,
+                            chunk_mesh,
+                        ));
+                    }
+                }
+            }
+            TileRenderMode::Sprites => {
+                let mut layer_indices = refreshed_layers;
+                layer_indices.sort_unstable();
+                layer_indices.dedup();
+                entities.0.retain(
+                    |entity| match tile_sprites.get::<TiledTileSprite>(*entity) {
+                        Ok(sprite) if layer_indices.contains(&sprite.layer_index) => {
+                            commands.despawn(*entity);
+                            false
+                        }
+                        _ => true,
+                    },
+                );
+                for layer_index in layer_indices {
+                    spawner.spawn_layer_sprites(&mut commands, layer_index, None, &mut spawned);
+                }
+            }
+        }
+        entities.0.extend(spawned);
+    }
+}
+
+/// Adds the images embedded in the tilesets of loaded maps to `Assets<Texture>`, under the
+/// handles their tiles are drawn with.
+pub fn add_embedded_images(
+    mut state: Local<MapResourceProviderState>,
+    map_events: Res<Events<AssetEvent<Map>>>,
+    maps: Res<Assets<Map>>,
+    mut textures: ResMut<Assets<Texture>>,
+) {
+    for event in state.map_event_reader.iter(&map_events) {
+        let handle = match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => handle,
+            AssetEvent::Removed { .. } => continue,
+        };
+        if let Some(map) = maps.get(handle) {
+            for image in map.tileset_embedded_images.values() {
+                textures.set(image.handle, image.texture.clone());
+            }
         }
     }
 }
@@ -125,6 +2146,10 @@ pub struct ChunkComponents {
     pub mesh: Handle<Mesh>,
     pub transform: Transform,
     pub global_transform: GlobalTransform,
+    pub animated_tiles: AnimatedTiles,
+    pub parallax: TiledLayerParallax,
+    pub render_chunk: RenderChunk,
+    pub position: TiledChunkPosition,
 }
 
 impl Default for ChunkComponents {
@@ -158,25 +2183,201 @@ impl Default for ChunkComponents {
             )]),
             transform: Default::default(),
             global_transform: Default::default(),
+            animated_tiles: Default::default(),
+            parallax: Default::default(),
+            render_chunk: Default::default(),
+            position: Default::default(),
+        }
+    }
+}
+
+/// Returns the transform a map entity's chunks are spawned with.
+fn tile_map_transform(
+    map: &Map,
+    center: &TiledMapCenter,
+    anchor: TiledMapAnchor,
+    origin: Transform,
+) -> Transform {
+    if center.0 {
+        map.center(origin)
+    } else {
+        map.anchor_transform(origin, anchor)
+    }
+}
+
+/// Spawns the tiles of a map entity's layers, when it is first spawned or a layer is refreshed.
+struct LayerSpawner<'a> {
+    map: &'a Map,
+    map_entity: TiledMapEntity,
+    materials: &'a HashMap<u32, Handle<ColorMaterial>>,
+    atlases: &'a HashMap<u32, Handle<TextureAtlas>>,
+    transform: Transform,
+    spawn_invisible_layers: bool,
+}
+
+impl LayerSpawner<'_> {
+    /// Spawns the entity drawing a chunk mesh, unless its layer is hidden and hidden layers
+    /// aren't spawned.
+    fn spawn_chunk_mesh(
+        &self,
+        commands: &mut Commands,
+        meshes: &mut Assets<Mesh>,
+        chunk_mesh: ChunkMesh,
+    ) -> Option<Entity> {
+        let layer = &self.map.layers[chunk_mesh.layer_id as usize];
+        if !layer.visible && !self.spawn_invisible_layers {
+            return None;
+        }
+        let material_handle = self.materials.get(&chunk_mesh.tileset_guid).unwrap();
+        // TODO: Sadly bevy doesn't support multiple meshes on a single entity with multiple materials.
+        // Change this once it does.
+
+        // Instead for now spawn a new entity per chunk.
+        commands.spawn(ChunkComponents {
+            chunk: TileMapChunk {
+                color: layer.color(),
+                // TODO: Support more layers here..
+                layer_id: chunk_mesh.layer_id as f32,
+            },
+            material: *material_handle,
+            mesh: meshes.add(chunk_mesh.mesh),
+            transform: self.transform,
+            animated_tiles: AnimatedTiles(chunk_mesh.animated_tiles),
+            parallax: TiledLayerParallax {
+                factor: Vec2::new(layer.parallax_x, layer.parallax_y),
+                origin: self.transform,
+            },
+            render_chunk: RenderChunk {
+                hidden: !layer.visible,
+                ..chunk_mesh.bounds
+            },
+            position: chunk_mesh.position,
+            draw: Draw {
+                is_visible: layer.visible,
+                is_transparent: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        commands.with(self.map_entity);
+        commands.current_entity()
+    }
+
+    /// Spawns a sprite for each tile of a layer, or only for the tile in `cell`, unless the
+    /// layer is hidden and hidden layers aren't spawned.
+    fn spawn_layer_sprites(
+        &self,
+        commands: &mut Commands,
+        layer_index: usize,
+        cell: Option<(u32, u32)>,
+        entities: &mut Vec<Entity>,
+    ) {
+        let layer = match self.map.layers.get(layer_index) {
+            Some(layer) if layer.visible || self.spawn_invisible_layers => layer,
+            _ => return,
+        };
+        let draw = Draw {
+            is_visible: layer.visible,
+            ..Default::default()
+        };
+        let parallax_factor = Vec2::new(layer.parallax_x, layer.parallax_y);
+        for tileset_layer in layer.tileset_layers.iter() {
+            let key = tileset_layer.tileset_guid;
+            for (chunk, tile) in tileset_layer
+                .chunks
+                .iter()
+                .flatten()
+                .flat_map(|chunk| chunk.tiles.iter().flatten().map(move |tile| (chunk, tile)))
+            {
+                let x = chunk.position.x() as u32 * CHUNK_TILES as u32 + tile.pos.x() as u32;
+                let y = chunk.position.y() as u32 * CHUNK_TILES as u32 + tile.pos.y() as u32;
+                if tile.tile_id == 0 || cell.is_some_and(|cell| cell != (x, y)) {
+                    continue;
+                }
+                let center = Vec2::new(
+                    (tile.vertex.x() + tile.vertex.z()) / 2.0,
+                    (tile.vertex.y() + tile.vertex.w()) / 2.0,
+                );
+                let flip = TileFlip {
+                    h: tile.flip_h,
+                    v: tile.flip_v,
+                    d: tile.flip_d,
+                };
+                let transform = Transform::new(
+                    *self.transform.value()
+                        * Mat4::from_translation(center.extend(layer_index as f32))
+                        * flip.transform(),
+                );
+                if let Some(texture_atlas) = self.atlases.get(&key) {
+                    commands.spawn(SpriteSheetComponents {
+                        texture_atlas: *texture_atlas,
+                        sprite: TextureAtlasSprite {
+                            color: layer.color(),
+                            index: tile.tile_id - key,
+                        },
+                        transform,
+                        draw: draw.clone(),
+                        ..Default::default()
+                    });
+                } else if let Some(material) = self.materials.get(&key) {
+                    // Sized from the tile rather than its texture, which may be a placeholder.
+                    let size = Vec2::new(
+                        (tile.vertex.z() - tile.vertex.x()).abs(),
+                        (tile.vertex.w() - tile.vertex.y()).abs(),
+                    );
+                    commands.spawn(SpriteComponents {
+                        sprite: Sprite::new(size),
+                        material: *material,
+                        transform,
+                        draw: draw.clone(),
+                        ..Default::default()
+                    });
+                } else {
+                    continue;
+                }
+                commands
+                    .with(TiledTileSprite { layer_index, x, y })
+                    .with(flip)
+                    .with(TiledLayerParallax {
+                        factor: parallax_factor,
+                        origin: transform,
+                    })
+                    .with(self.map_entity);
+                entities.extend(commands.current_entity());
+            }
         }
     }
 }
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn process_loaded_tile_maps(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    mut textures: ResMut<TiledTextures>,
     mut state: Local<MapResourceProviderState>,
     map_events: Res<Events<AssetEvent<Map>>>,
-    mut maps: ResMut<Assets<Map>>,
+    maps: Res<Assets<Map>>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    mut clear_color: ResMut<ClearColor>,
+    mut map_spawned_events: ResMut<Events<MapSpawned>>,
     mut query: Query<(
         Entity,
         &TiledMapCenter,
+        &TiledMapAnchor,
+        &TiledMapClearColor,
+        &TiledMapMergeLayers,
+        &TileRenderMode,
+        &TiledMapSpawnInvisibleLayers,
+        &TiledMapColliders,
         &Handle<Map>,
         &mut HashMap<u32, Handle<ColorMaterial>>,
+        &mut HashMap<u32, Handle<TextureAtlas>>,
         &Transform,
+        &mut TiledMapEntities,
     )>,
+    mesh_handles: Query<&Handle<Mesh>>,
 ) {
     let mut changed_maps = HashSet::<Handle<Map>>::new();
     for event in state.map_event_reader.iter(&map_events) {
@@ -185,6 +2386,8 @@ pub fn process_loaded_tile_maps(
                 changed_maps.insert(*handle);
             }
             AssetEvent::Modified { handle } => {
+                // The reload may have been made to fix the map's missing images.
+                textures.forget_missing();
                 changed_maps.insert(*handle);
             }
             AssetEvent::Removed { handle } => {
@@ -195,75 +2398,260 @@ pub fn process_loaded_tile_maps(
         }
     }
 
-    let mut new_meshes = HashMap::<&Handle<Map>, Vec<(u32, u32, Handle<Mesh>)>>::new();
-    for changed_map in changed_maps.iter() {
-        let map = maps.get_mut(changed_map).unwrap();
+    for (
+        map_entity,
+        center,
+        anchor,
+        use_clear_color,
+        merge_tile_layers,
+        render_mode,
+        spawn_invisible_layers,
+        colliders,
+        map_handle,
+        mut materials_map,
+        mut atlases_map,
+        origin,
+        mut entities,
+    ) in &mut query.iter()
+    {
+        // Tags everything spawned for this map entity.
+        let spawned_for = TiledMapEntity {
+            map: *map_handle,
+            entity: map_entity,
+        };
 
-        for (_, _, _, mut materials_map, _) in &mut query.iter() {
-            for tileset in &map.map.tilesets {
-                if !materials_map.contains_key(&tileset.first_gid) {
-                    let texture_path =
-                        map.image_folder.clone() + "/" + &tileset.images.first().unwrap().source;
-                    let texture_handle = asset_server.load(texture_path).unwrap();
-                    materials_map.insert(tileset.first_gid, materials.add(texture_handle.into()));
-                }
+        if !changed_maps.contains(map_handle) {
+            continue;
+        }
+        let map = maps.get(map_handle).unwrap();
+
+        // Replace whatever was spawned for the previous version of the map.
+        for entity in entities.0.drain(..) {
+            if let Ok(mesh_handle) = mesh_handles.get::<Handle<Mesh>>(entity) {
+                meshes.remove(&mesh_handle);
             }
+            commands.despawn(entity);
         }
 
-        for mesh in map.meshes.drain(0..map.meshes.len()) {
-            let handle = meshes.add(mesh.2);
-            if new_meshes.contains_key(changed_map) {
-                let mesh_list = new_meshes.get_mut(changed_map).unwrap();
-                mesh_list.push((mesh.0, mesh.1, handle));
-            } else {
-                let mut mesh_list = Vec::new();
-                mesh_list.push((mesh.0, mesh.1, handle));
-                new_meshes.insert(changed_map, mesh_list);
+        if use_clear_color.0 {
+            if let Some(background_color) = map.background_color {
+                clear_color.0 = background_color;
             }
         }
-    }
 
-    for (_, center, map_handle, materials_map, origin) in &mut query.iter() {
-        if new_meshes.contains_key(map_handle) {
-            let map = maps.get(map_handle).unwrap();
+        let tileset_textures = map
+            .tileset_image_paths
+            .iter()
+            .map(|(first_gid, path)| (first_gid, textures.load(&asset_server, path)))
+            .chain(
+                map.tileset_embedded_images
+                    .iter()
+                    .map(|(first_gid, image)| (first_gid, image.handle)),
+            )
+            .collect::<Vec<_>>();
+        for (first_gid, texture_handle) in tileset_textures {
+            materials_map
+                .entry(*first_gid)
+                .or_insert_with(|| materials.add(texture_handle.into()));
+            if let (Some(texture_atlas), Some(texture_atlas_handle)) = (
+                map.tileset_texture_atlas(*first_gid, texture_handle),
+                map.tileset_atlases.get(first_gid),
+            ) {
+                texture_atlases.set(*texture_atlas_handle, texture_atlas);
+                // A reloaded map has new atlases, as its tileset may have changed.
+                if let Some(previous) = atlases_map.insert(*first_gid, *texture_atlas_handle) {
+                    if previous != *texture_atlas_handle {
+                        texture_atlases.remove(&previous);
+                    }
+                }
+            }
+        }
 
-            let tile_map_transform = if center.0 {
-                map.center(origin.clone())
-            } else {
-                origin.clone()
-            };
+        let tile_map_transform = tile_map_transform(map, center, *anchor, *origin);
 
-            let mesh_list = new_meshes.get_mut(map_handle).unwrap();
+        for (layer_index, layer) in map.layers.iter().enumerate() {
+            commands.spawn((
+                TiledLayerVisibility {
+                    layer_index,
+                    visible: layer.visible,
+                },
+                spawned_for,
+            ));
+            entities.0.extend(commands.current_entity());
+        }
 
-            for (layer_id, layer) in map.layers.iter().enumerate() {
-                for tileset_layer in layer.tileset_layers.iter() {
-                    let material_handle = materials_map.get(&tileset_layer.tileset_guid).unwrap();
-                    // let mut mesh_list = mesh_list.iter_mut().filter(|(mesh_layer_id, _)| *mesh_layer_id == layer_id as u32).drain(0..mesh_list.len()).collect::<Vec<_>>();
-                    let chunk_mesh_list = mesh_list
-                        .iter()
-                        .filter(|(mesh_layer_id, tileset_guid, _)| {
-                            *mesh_layer_id == layer_id as u32
-                                && *tileset_guid == tileset_layer.tileset_guid
-                        })
-                        .collect::<Vec<_>>();
-                    for (_, _, mesh) in chunk_mesh_list.iter() {
-                        // TODO: Sadly bevy doesn't support multiple meshes on a single entity with multiple materials.
-                        // Change this once it does.
-
-                        // Instead for now spawn a new entity per chunk.
-                        commands.spawn(ChunkComponents {
-                            chunk: TileMapChunk {
-                                // TODO: Support more layers here..
-                                layer_id: layer_id as f32,
-                            },
-                            material: material_handle.clone(),
-                            mesh: mesh.clone(),
-                            transform: tile_map_transform.clone(),
-                            ..Default::default()
+        let spawner = LayerSpawner {
+            map,
+            map_entity: spawned_for,
+            materials: &materials_map,
+            atlases: &atlases_map,
+            transform: tile_map_transform,
+            spawn_invisible_layers: spawn_invisible_layers.0,
+        };
+        match render_mode {
+            TileRenderMode::Mesh => {
+                for chunk_mesh in map.build_meshes(merge_tile_layers) {
+                    entities.0.extend(spawner.spawn_chunk_mesh(
+                        &mut commands,
+                        &mut meshes,
+                        chunk_mesh,
+                    ));
+                }
+            }
+            TileRenderMode::Sprites => {
+                for layer_index in 0..map.layers.len() {
+                    spawner.spawn_layer_sprites(&mut commands, layer_index, None, &mut entities.0);
+                }
+            }
+        }
+
+        for image_layer in map.image_layers.iter() {
+            let image = match &image_layer.image {
+                Some(image) if image_layer.visible => image,
+                _ => continue,
+            };
+            let texture_handle = textures.load(&asset_server, image);
+            let material = materials.add(ColorMaterial {
+                color: image_layer.color(),
+                texture: Some(texture_handle),
+            });
+            // Sprites are centered on their transform, so place the image's center.
+            let size = image_layer.size;
+            let position =
+                map.screen_to_world(image_layer.offset) + Vec2::new(size.x(), -size.y()) / 2.0;
+            commands
+                .spawn(SpriteComponents {
+                    sprite: Sprite::new(size),
+                    material,
+                    transform: Transform::new(
+                        *tile_map_transform.value()
+                            * Mat4::from_translation(position.extend(image_layer.z)),
+                    ),
+                    ..Default::default()
+                })
+                .with(spawned_for);
+            entities.0.extend(commands.current_entity());
+        }
+
+        // Objects are placed above the tile layers, each object layer above the previous one.
+        let object_z = map
+            .object_layers
+            .iter()
+            .enumerate()
+            .flat_map(|(object_layer_index, object_layer)| {
+                let layer_z = (map.layers.len() + object_layer_index) as f32;
+                object_layer
+                    .draw_depths()
+                    .into_iter()
+                    .map(move |depth| layer_z + depth)
+            })
+            .collect::<Vec<_>>();
+        for ((object_layer, object), z) in map.all_objects().zip(object_z) {
+            // Hidden objects are often editor-only notes, so they aren't spawned.
+            if !object_layer.visible || !object.visible {
+                continue;
+            }
+            if let Some(tileset) = map.tileset_by_gid(object.gid) {
+                let local_id = object.gid - tileset.first_gid;
+                let sprite_transform = |image_size: Vec2| {
+                    Transform::new(
+                        *tile_map_transform.value()
+                            * map.tile_object_sprite_transform(object, image_size, z),
+                    )
+                };
+                if let Some(texture_atlas) = atlases_map.get(&tileset.first_gid) {
+                    let tile_size =
+                        Vec2::new(tileset.tile_width as f32, tileset.tile_height as f32);
+                    let animation = map
+                        .tile_animations
+                        .get(&object.gid)
+                        .filter(|animation| !animation.frames.is_empty());
+                    // Animated tiles start on their first frame, like in chunk meshes.
+                    let index = animation.map_or(local_id, |animation| animation.frames[0].0);
+                    commands.spawn(SpriteSheetComponents {
+                        texture_atlas: *texture_atlas,
+                        sprite: TextureAtlasSprite::new(index),
+                        transform: sprite_transform(tile_size),
+                        ..Default::default()
+                    });
+                    if let Some(animation) = animation {
+                        commands.with(AnimatedTileSprite {
+                            animation: animation.clone(),
+                            current_frame: 0,
                         });
                     }
+                } else if let (Some(material), Some(image_size)) = (
+                    materials_map.get(&object.gid),
+                    map.tileset_image_sizes.get(&object.gid),
+                ) {
+                    commands.spawn(SpriteComponents {
+                        sprite: Sprite::new(*image_size),
+                        material: *material,
+                        transform: sprite_transform(*image_size),
+                        ..Default::default()
+                    });
+                } else {
+                    continue;
                 }
+                commands.with(object.clone()).with(spawned_for);
+                entities.0.extend(commands.current_entity());
+                continue;
+            }
+
+            commands.spawn(ObjectComponents {
+                object: object.clone(),
+                transform: Transform::new(
+                    *tile_map_transform.value() * map.object_transform(object, z),
+                ),
+                global_transform: Default::default(),
+                map_entity: spawned_for,
+            });
+            entities.0.extend(commands.current_entity());
+        }
+
+        if let Some(layer_name) = &colliders.layer {
+            for rect in map.collider_rects(layer_name, colliders.merge) {
+                let (center, size) = map.tile_rect_to_world(rect);
+                commands.spawn((
+                    TiledCollider { rect, size },
+                    Transform::new(
+                        *tile_map_transform.value() * Mat4::from_translation(center.extend(0.0)),
+                    ),
+                    GlobalTransform::default(),
+                    spawned_for,
+                ));
+                entities.0.extend(commands.current_entity());
             }
         }
+
+        map_spawned_events.send(MapSpawned {
+            map: *map_handle,
+            entity: map_entity,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn objects_rotate_clockwise_like_in_tiled() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/maps/save.tmx");
+        let map = Map::from_bytes(path, std::fs::read(path).unwrap()).unwrap();
+        let object = TiledObject {
+            rotation: 90.0,
+            ..map.object_by_name("Spawn").unwrap().clone()
+        };
+
+        let transform = map.object_transform(&object, 3.0);
+        let (_, rotation, translation) = transform.to_scale_rotation_translation();
+        assert_eq!(translation, map.pixel_to_world(object.position).extend(3.0));
+        let (axis, angle) = rotation.to_axis_angle();
+        assert!((axis.z().abs() - 1.0).abs() < 1e-5);
+        assert!((angle * axis.z() + std::f32::consts::FRAC_PI_2).abs() < 1e-5);
+        // The object's right points down the screen, with y up in bevy.
+        let right = transform.transform_vector3(Vec3::unit_x());
+        assert!((right - Vec3::new(0.0, -1.0, 0.0)).length() < 1e-5);
     }
 }
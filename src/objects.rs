@@ -0,0 +1,228 @@
+use crate::{
+    loader::{
+        ALL_FLIP_FLAGS, FLIPPED_DIAGONALLY_FLAG, FLIPPED_HORIZONTALLY_FLAG, FLIPPED_VERTICALLY_FLAG,
+    },
+    PropertyValue, TiledProperties,
+};
+use bevy::render::color::Color;
+use glam::Vec2;
+
+/// An `<objectgroup>` layer and the objects placed on it.
+#[derive(Debug, Clone)]
+pub struct TiledObjectLayer {
+    /// The layer's id, unique across the map and kept when layers are renamed or moved. Maps
+    /// saved before Tiled 1.2 don't have layer ids.
+    pub id: Option<u32>,
+    pub name: String,
+    pub class: Option<String>,
+    pub opacity: f32,
+    pub visible: bool,
+    pub draw_order: TiledDrawOrder,
+    pub properties: TiledProperties,
+    pub objects: Vec<TiledObject>,
+}
+
+impl TiledObjectLayer {
+    /// Returns how far into the layer each of its `objects` is drawn, from 0.0 for the object
+    /// drawn first up to below 1.0, following the layer's `draw_order`.
+    ///
+    /// Spawned objects are placed at their layer's z plus this, so objects drawn later are in
+    /// front.
+    pub fn draw_depths(&self) -> Vec<f32> {
+        let mut order = (0..self.objects.len()).collect::<Vec<_>>();
+        if self.draw_order == TiledDrawOrder::TopDown {
+            // The sort is stable, so objects at the same y keep their order.
+            order.sort_by(|a, b| {
+                let (a, b) = (self.objects[*a].position.y(), self.objects[*b].position.y());
+                a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+            });
+        }
+        let mut depths = vec![0.0; self.objects.len()];
+        for (rank, object_index) in order.into_iter().enumerate() {
+            depths[object_index] = rank as f32 / self.objects.len() as f32;
+        }
+        depths
+    }
+}
+
+/// The order the objects of an object layer are drawn in, which decides how overlapping
+/// objects stack.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TiledDrawOrder {
+    /// Objects further down are drawn in front, like Tiled does for new object layers.
+    #[default]
+    TopDown,
+    /// Objects are drawn in the order they are listed in the layer.
+    Index,
+}
+
+impl From<&tiled::ObjectGroup> for TiledObjectLayer {
+    fn from(object_group: &tiled::ObjectGroup) -> Self {
+        Self {
+            id: None,
+            name: object_group.name.clone(),
+            class: None,
+            opacity: object_group.opacity,
+            visible: object_group.visible,
+            draw_order: Default::default(),
+            properties: Default::default(),
+            objects: object_group.objects.iter().map(TiledObject::from).collect(),
+        }
+    }
+}
+
+/// An object from an object layer.
+///
+/// Positions and sizes are in map pixels with y pointing down, as they are stored in the map.
+/// They keep the fractional and negative values Tiled saves, without rounding to whole pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TiledObject {
+    /// The object's id, unique across the map.
+    pub id: u32,
+    /// The gid of the tile this object displays without its flip flags, or 0 if it doesn't
+    /// display one.
+    ///
+    /// Tile objects are anchored at the bottom left of the tile in orthogonal maps, and at the
+    /// bottom center in isometric maps.
+    pub gid: u32,
+    pub flip_h: bool,
+    pub flip_v: bool,
+    pub flip_d: bool,
+    pub name: String,
+    /// The object's `type`, which Tiled 1.9 renamed to `class`. Only set for maps saved before
+    /// the rename, prefer `class`.
+    pub obj_type: String,
+    /// The object's class, read from `type` in maps saved before Tiled 1.9.
+    pub class: Option<String>,
+    pub position: Vec2,
+    pub size: Vec2,
+    /// The clockwise rotation around `position`, in degrees.
+    pub rotation: f32,
+    pub visible: bool,
+    pub shape: TiledObjectShape,
+    pub properties: TiledProperties,
+}
+
+impl From<&tiled::Object> for TiledObject {
+    fn from(object: &tiled::Object) -> Self {
+        Self {
+            id: object.id,
+            gid: object.gid & !ALL_FLIP_FLAGS,
+            flip_h: object.gid & FLIPPED_HORIZONTALLY_FLAG != 0,
+            flip_v: object.gid & FLIPPED_VERTICALLY_FLAG != 0,
+            flip_d: object.gid & FLIPPED_DIAGONALLY_FLAG != 0,
+            name: object.name.clone(),
+            obj_type: object.obj_type.clone(),
+            class: Some(object.obj_type.clone()).filter(|class| !class.is_empty()),
+            position: Vec2::new(object.x, object.y),
+            size: Vec2::new(object.width, object.height),
+            rotation: object.rotation,
+            visible: object.visible,
+            shape: TiledObjectShape::from(&object.shape),
+            properties: Default::default(),
+        }
+    }
+}
+
+impl TiledObject {
+    /// Returns the custom property with the given name.
+    pub fn property(&self, name: &str) -> Option<&PropertyValue> {
+        self.properties.get(name)
+    }
+
+    /// Returns the value of a `float` or `int` property, or `None` if the object doesn't have
+    /// one with the given name.
+    pub fn property_f32(&self, name: &str) -> Option<f32> {
+        self.property(name).and_then(PropertyValue::as_f32)
+    }
+
+    /// Returns the value of a `bool` property, or `None` if the object doesn't have one with the
+    /// given name.
+    pub fn property_bool(&self, name: &str) -> Option<bool> {
+        self.property(name).and_then(PropertyValue::as_bool)
+    }
+
+    /// Returns the value of a `string` property, or `None` if the object doesn't have one with
+    /// the given name.
+    pub fn property_string(&self, name: &str) -> Option<&str> {
+        self.property(name).and_then(PropertyValue::as_str)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TiledObjectShape {
+    /// A rectangle spanning `size`, with `position` at its top left corner.
+    Rect,
+    /// An ellipse inscribed in the rectangle a `Rect` with the same size would cover.
+    Ellipse,
+    /// A single point at `position`, such as a spawn location.
+    Point,
+    /// An open path through the points, relative to the object's position.
+    Polyline(Vec<Vec2>),
+    /// A closed path through the points, relative to the object's position.
+    Polygon(Vec<Vec2>),
+    /// Text laid out in the rectangle a `Rect` with the same size would cover.
+    Text(TiledObjectText),
+}
+
+/// The text of a text object and how Tiled lays it out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TiledObjectText {
+    pub text: String,
+    pub font_family: String,
+    /// The font size in pixels.
+    pub pixel_size: f32,
+    pub color: Color,
+    /// Whether lines wrap at the object's width.
+    pub wrap: bool,
+    pub bold: bool,
+    pub italic: bool,
+    pub halign: TextHAlign,
+    pub valign: TextVAlign,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextHAlign {
+    Left,
+    Center,
+    Right,
+    Justify,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextVAlign {
+    Top,
+    Center,
+    Bottom,
+}
+
+impl TiledObjectShape {
+    /// Returns the vertices of a polyline or polygon in the order they were drawn in Tiled.
+    ///
+    /// Like the object's position, the points are in map pixels with y pointing down. Other
+    /// shapes have no points.
+    pub fn points(&self) -> &[Vec2] {
+        match self {
+            TiledObjectShape::Polyline(points) | TiledObjectShape::Polygon(points) => points,
+            _ => &[],
+        }
+    }
+}
+
+impl From<&tiled::ObjectShape> for TiledObjectShape {
+    fn from(shape: &tiled::ObjectShape) -> Self {
+        let to_vec2 = |points: &[(f32, f32)]| {
+            points
+                .iter()
+                .map(|(x, y)| Vec2::new(*x, *y))
+                .collect::<Vec<_>>()
+        };
+        match shape {
+            tiled::ObjectShape::Rect { .. } => TiledObjectShape::Rect,
+            tiled::ObjectShape::Ellipse { .. } => TiledObjectShape::Ellipse,
+            tiled::ObjectShape::Point(..) => TiledObjectShape::Point,
+            tiled::ObjectShape::Polyline { points } => TiledObjectShape::Polyline(to_vec2(points)),
+            tiled::ObjectShape::Polygon { points } => TiledObjectShape::Polygon(to_vec2(points)),
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use bevy::{
+    prelude::*,
+    render::{camera::Camera, render_graph::base::camera::CAMERA2D},
+};
+use glam::Vec2;
+
+/// Scrolls a chunk entity with its layer's parallax factor as the 2d camera moves.
+///
+/// A factor of 1.0 moves the layer with the rest of the map, smaller factors make it scroll
+/// slower and 0.0 keeps it fixed on screen.
+pub struct TiledLayerParallax {
+    pub factor: Vec2,
+    /// The transform of the chunk when the camera is at the origin.
+    pub origin: Transform,
+}
+
+impl Default for TiledLayerParallax {
+    fn default() -> Self {
+        Self {
+            factor: Vec2::one(),
+            origin: Transform::identity(),
+        }
+    }
+}
+
+/// Returns how far a layer is moved from its position when the camera is at `camera_position`.
+pub fn parallax_offset(camera_position: Vec2, factor: Vec2) -> Vec2 {
+    camera_position * (Vec2::one() - factor)
+}
+
+pub fn apply_layer_parallax(
+    mut cameras: Query<(&Camera, &Transform)>,
+    mut query: Query<(&TiledLayerParallax, &mut Transform)>,
+) {
+    let mut camera_position = None;
+    for (camera, transform) in &mut cameras.iter() {
+        if camera.name.as_deref() == Some(CAMERA2D) {
+            camera_position = Some(transform.translation().truncate());
+        }
+    }
+    let camera_position = match camera_position {
+        Some(camera_position) => camera_position,
+        None => return,
+    };
+
+    for (parallax, mut transform) in &mut query.iter() {
+        if parallax.factor == Vec2::one() {
+            continue;
+        }
+        let offset = parallax_offset(camera_position, parallax.factor);
+        *transform =
+            Transform::new(Mat4::from_translation(offset.extend(0.0)) * *parallax.origin.value());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::Schedule;
+
+    #[test]
+    fn layers_scroll_by_their_factor() {
+        let camera_position = Vec2::new(100.0, -40.0);
+
+        // Fixed on screen, so moved along with the camera.
+        assert_eq!(
+            parallax_offset(camera_position, Vec2::zero()),
+            camera_position
+        );
+        // Scrolled at half speed, so moved half as far.
+        assert_eq!(
+            parallax_offset(camera_position, Vec2::new(0.5, 0.5)),
+            Vec2::new(50.0, -20.0)
+        );
+        // Scrolled with the map.
+        assert_eq!(parallax_offset(camera_position, Vec2::one()), Vec2::zero());
+        // Each axis has its own factor.
+        assert_eq!(
+            parallax_offset(camera_position, Vec2::new(1.0, 0.0)),
+            Vec2::new(0.0, -40.0)
+        );
+    }
+
+    #[test]
+    fn layers_are_moved_from_their_origin() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        world.spawn((
+            Camera {
+                name: Some(CAMERA2D.to_string()),
+                ..Default::default()
+            },
+            Transform::from_translation(Vec3::new(100.0, -40.0, 0.0)),
+        ));
+        let layer = world.spawn((
+            TiledLayerParallax {
+                factor: Vec2::new(0.5, 0.5),
+                origin: Transform::from_translation(Vec3::new(10.0, 0.0, 2.0)),
+            },
+            Transform::identity(),
+        ));
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update");
+        schedule.add_system_to_stage("update", apply_layer_parallax.system());
+        schedule.run(&mut world, &mut resources);
+
+        assert_eq!(
+            world.get::<Transform>(layer).unwrap().translation(),
+            Vec3::new(60.0, -20.0, 2.0)
+        );
+    }
+}
@@ -65,7 +65,7 @@ pub fn build_tile_map_pipeline(shaders: &mut Assets<Shader>) -> PipelineDescript
 }
 
 pub mod node {
-    pub const TILE_MAP_CHUNK: &'static str = "tile_map_chunk";
+    pub const TILE_MAP_CHUNK: &str = "tile_map_chunk";
 }
 
 pub trait TileMapRenderGraphBuilder {
@@ -0,0 +1,44 @@
+use bevy::render::color::Color;
+use std::{collections::HashMap, path::PathBuf};
+
+/// Custom properties set in Tiled, keyed by name.
+pub type TiledProperties = HashMap<String, PropertyValue>;
+
+/// The value of a custom property, typed by the property's `type` attribute.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyValue {
+    String(String),
+    Int(i64),
+    Float(f32),
+    Bool(bool),
+    Color(Color),
+    /// A file path, resolved relative to the file the property was defined in.
+    File(PathBuf),
+}
+
+impl PropertyValue {
+    /// Returns the value of a `string` property.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            PropertyValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a `float` property, or of an `int` property converted to `f32`.
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            PropertyValue::Float(value) => Some(*value),
+            PropertyValue::Int(value) => Some(*value as f32),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a `bool` property.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            PropertyValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use crate::{element::Element, extras::resolve_path, json, loader::ALL_FLIP_FLAGS, TiledError};
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+};
+
+/// An object template read from a `.tx` or `.tj` file.
+struct Template {
+    path: PathBuf,
+    /// The first gid and resolved path of the tileset the template's tile is from.
+    tileset: Option<(u32, PathBuf)>,
+    object: Element,
+}
+
+/// Replaces the `template` attribute of each object in a map with the template's attributes,
+/// properties and shape, where the object doesn't override them.
+///
+/// Template instances only store what was changed in Tiled, so this gives them the same
+/// elements as a plain object before the map is parsed.
+pub fn apply_templates(map: &mut Element, map_path: &Path) -> Result<(), TiledError> {
+    // Template tiles are from the template's reference to their tileset, so their gids are
+    // moved to the map's reference to the same tileset.
+    let map_tilesets = map
+        .children_named("tileset")
+        .filter_map(|tileset| {
+            let first_gid = tileset.attribute::<u32>("firstgid")?;
+            let source = tileset.attribute::<String>("source")?;
+            Some((resolve_path(map_path, &source), first_gid))
+        })
+        .collect::<HashMap<_, _>>();
+    let mut templates = HashMap::new();
+    apply_templates_to(map, map_path, &map_tilesets, &mut templates)
+}
+
+fn apply_templates_to(
+    element: &mut Element,
+    map_path: &Path,
+    map_tilesets: &HashMap<PathBuf, u32>,
+    templates: &mut HashMap<PathBuf, Template>,
+) -> Result<(), TiledError> {
+    for child in element.children.iter_mut() {
+        if child.name == "object" {
+            if let Some(source) = child.attribute::<String>("template") {
+                let template_path = resolve_path(map_path, &source);
+                if !templates.contains_key(&template_path) {
+                    let template = read_template(&template_path)?;
+                    templates.insert(template_path.clone(), template);
+                }
+                apply_template(child, &templates[&template_path], map_path, map_tilesets)?;
+            }
+        }
+        apply_templates_to(child, map_path, map_tilesets, templates)?;
+    }
+    Ok(())
+}
+
+fn read_template(path: &Path) -> Result<Template, TiledError> {
+    let bytes = std::fs::read(path).map_err(|error| TiledError::Io {
+        path: path.to_path_buf(),
+        error,
+    })?;
+    let template = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("tj") | Some("json") => json::parse_template(&bytes),
+        _ => Element::parse(&bytes),
+    }
+    .map_err(TiledError::in_file(path))?;
+
+    let tileset = template.child("tileset").and_then(|tileset| {
+        let first_gid = tileset.attribute::<u32>("firstgid")?;
+        let source = tileset.attribute::<String>("source")?;
+        Some((first_gid, resolve_path(path, &source)))
+    });
+    let object = match template.child("object") {
+        Some(object) => object.clone(),
+        None => return Err(invalid_template(path, "has no object")),
+    };
+    Ok(Template {
+        path: path.to_path_buf(),
+        tileset,
+        object,
+    })
+}
+
+/// Fills in what an object doesn't override from its template. Attributes and properties are
+/// merged one by one, while shapes and text are taken whole from the template if the object
+/// doesn't have its own.
+fn apply_template(
+    object: &mut Element,
+    template: &Template,
+    map_path: &Path,
+    map_tilesets: &HashMap<PathBuf, u32>,
+) -> Result<(), TiledError> {
+    object.attributes.retain(|(name, _)| name != "template");
+    for (name, value) in template.object.attributes.iter() {
+        if name == "id" || object.attribute::<String>(name).is_some() {
+            continue;
+        }
+        let value = if name == "gid" {
+            map_gid(template, value, map_tilesets)?
+        } else {
+            value.clone()
+        };
+        object.set_attribute(name, value);
+    }
+
+    if let Some(template_properties) = template.object.child("properties") {
+        let properties = template_properties
+            .children_named("property")
+            .map(|property| template_property(property, &template.path, map_path))
+            .collect::<Vec<_>>();
+        match object
+            .children
+            .iter_mut()
+            .find(|child| child.name == "properties")
+        {
+            Some(object_properties) => {
+                for property in properties {
+                    let name = property.attribute::<String>("name");
+                    if !object_properties
+                        .children_named("property")
+                        .any(|overridden| overridden.attribute::<String>("name") == name)
+                    {
+                        object_properties.children.push(property);
+                    }
+                }
+            }
+            None => object.children.insert(
+                0,
+                Element {
+                    name: "properties".to_string(),
+                    children: properties,
+                    ..Default::default()
+                },
+            ),
+        }
+    }
+
+    for child in template.object.children.iter() {
+        if child.name != "properties" && object.child(&child.name).is_none() {
+            object.children.push(child.clone());
+        }
+    }
+    Ok(())
+}
+
+/// Moves a template's gid from its own reference to the tileset to the map's, keeping its
+/// flip flags.
+fn map_gid(
+    template: &Template,
+    gid: &str,
+    map_tilesets: &HashMap<PathBuf, u32>,
+) -> Result<String, TiledError> {
+    let invalid_gid = || invalid_template(&template.path, "has an invalid gid");
+    let gid = gid.parse::<u32>().map_err(|_| invalid_gid())?;
+    let (template_first_gid, tileset_path) = match &template.tileset {
+        Some(tileset) => tileset,
+        None => {
+            return Err(invalid_template(
+                &template.path,
+                "has a tile but no tileset",
+            ))
+        }
+    };
+    let map_first_gid = match map_tilesets.get(tileset_path) {
+        Some(first_gid) => *first_gid,
+        None => {
+            return Err(invalid_template(
+                &template.path,
+                &format!("uses tileset {:?}, which the map doesn't", tileset_path),
+            ))
+        }
+    };
+    let local_id = (gid & !ALL_FLIP_FLAGS)
+        .checked_sub(*template_first_gid)
+        .ok_or_else(invalid_gid)?;
+    Ok(((map_first_gid + local_id) | (gid & ALL_FLIP_FLAGS)).to_string())
+}
+
+/// Returns a template's property as the map would store it. File properties are relative to
+/// the template, so they are rewritten relative to the map.
+fn template_property(property: &Element, template_path: &Path, map_path: &Path) -> Element {
+    let mut property = property.clone();
+    if property.attribute::<String>("type").as_deref() == Some("file") {
+        if let Some(value) = property.attribute::<String>("value") {
+            let path = resolve_path(template_path, &value);
+            let relative = relative_path(map_path.parent().unwrap_or_else(|| Path::new("")), &path);
+            property.set_attribute("value", relative.to_string_lossy().into_owned());
+        }
+    }
+    property
+}
+
+/// Returns `path` relative to the directory `from`, where both were resolved from the same
+/// map path.
+fn relative_path(from: &Path, path: &Path) -> PathBuf {
+    let from = from.components().collect::<Vec<_>>();
+    let to = path.components().collect::<Vec<_>>();
+    let common = from
+        .iter()
+        .zip(to.iter())
+        .take_while(|(from, to)| from == to)
+        .count();
+    let mut relative = PathBuf::new();
+    for component in &from[common..] {
+        match component {
+            Component::Normal(_) => relative.push(".."),
+            // Directories above `from` can't be walked back down from.
+            _ => return path.to_path_buf(),
+        }
+    }
+    for component in &to[common..] {
+        relative.push(component);
+    }
+    relative
+}
+
+fn invalid_template(path: &Path, reason: &str) -> TiledError {
+    TiledError::InvalidTemplate {
+        path: path.to_path_buf(),
+        reason: reason.to_string(),
+    }
+}
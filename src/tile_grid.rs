@@ -0,0 +1,43 @@
+/// A 2d view of the gids of a tile layer, borrowed from its map.
+///
+/// Gids are returned without flip flags, with 0 for empty cells.
+#[derive(Debug, Clone, Copy)]
+pub struct TileGrid<'a> {
+    rows: &'a [Vec<tiled::LayerTile>],
+    width: u32,
+    height: u32,
+}
+
+impl<'a> TileGrid<'a> {
+    pub(crate) fn new(rows: &'a [Vec<tiled::LayerTile>], width: u32, height: u32) -> Self {
+        Self {
+            rows,
+            width,
+            height,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the gid at `x`, `y`, or `None` if the position is outside the grid.
+    pub fn get(&self, x: u32, y: u32) -> Option<u32> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.rows
+            .get(y as usize)
+            .and_then(|row| row.get(x as usize))
+            .map(|tile| tile.gid)
+    }
+
+    /// Iterates the rows from the top, each yielding its gids from the left.
+    pub fn rows(&self) -> impl Iterator<Item = impl Iterator<Item = u32> + 'a> + 'a {
+        self.rows.iter().map(|row| row.iter().map(|tile| tile.gid))
+    }
+}
@@ -1,10 +1,15 @@
 use bevy::core::Byteable;
-use bevy::render::renderer::{RenderResource, RenderResources};
+use bevy::render::{
+    color::Color,
+    renderer::{RenderResource, RenderResources},
+};
 
 #[repr(C)]
 #[derive(Default, RenderResources, RenderResource)]
 #[render_resources(from_self)]
 pub struct TileMapChunk {
+    /// Multiplied with the color of every tile in the chunk.
+    pub color: Color,
     pub layer_id: f32,
 }
 
@@ -0,0 +1,48 @@
+use crate::{Map, RenderChunk, TileMapChunk, TiledMapEntity, TiledMapMergeLayers, TiledTileSprite};
+use bevy::prelude::*;
+
+/// Shows or hides one of a map's tile layers at runtime.
+///
+/// An entity with this component is spawned for each layer in `Map::layers`, starting out with
+/// the layer's visibility. Changing `visible` hides or shows the layer's chunks or tile sprites.
+/// Hidden layers only have chunks to show if the map was spawned with
+/// `TiledMapSpawnInvisibleLayers`. Merged layers share their chunks, so hiding or showing one
+/// hides or shows every layer merged with it.
+#[derive(Debug, Clone)]
+pub struct TiledLayerVisibility {
+    /// The index of the layer in `Map::layers`.
+    pub layer_index: usize,
+    pub visible: bool,
+}
+
+pub fn apply_layer_visibility(
+    maps: Res<Assets<Map>>,
+    mut layers: Query<(Changed<TiledLayerVisibility>, &TiledMapEntity)>,
+    merges: Query<&TiledMapMergeLayers>,
+    mut chunks: Query<(&TileMapChunk, &TiledMapEntity, &mut RenderChunk, &mut Draw)>,
+    mut sprites: Query<(&TiledTileSprite, &TiledMapEntity, &mut Draw)>,
+) {
+    for (layer_visibility, layer_map) in &mut layers.iter() {
+        // Chunks of merged layers carry the `layer_id` of the first layer in their group.
+        let mesh_layer_id = match (
+            maps.get(&layer_map.map),
+            merges.get::<TiledMapMergeLayers>(layer_map.entity),
+        ) {
+            (Some(map), Ok(merge)) => map.mesh_layer_id(&merge, layer_visibility.layer_index),
+            _ => layer_visibility.layer_index,
+        };
+        for (chunk, chunk_map, mut render_chunk, mut draw) in &mut chunks.iter() {
+            if chunk_map != layer_map || chunk.layer_id as usize != mesh_layer_id {
+                continue;
+            }
+            render_chunk.hidden = !layer_visibility.visible;
+            // Chunks outside of the view are shown again by `cull_chunks` once they are in view.
+            draw.is_visible = layer_visibility.visible;
+        }
+        for (sprite, sprite_map, mut draw) in &mut sprites.iter() {
+            if sprite_map == layer_map && sprite.layer_index == layer_visibility.layer_index {
+                draw.is_visible = layer_visibility.visible;
+            }
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use crate::TiledProperties;
+use bevy::render::color::Color;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WangSetType {
+    /// Tiles are matched by the colors of their corners.
+    Corner,
+    /// Tiles are matched by the colors of their edges.
+    Edge,
+    /// Tiles are matched by the colors of both their corners and edges.
+    Mixed,
+}
+
+/// A set of terrains from a tileset's `<wangsets>`, which Tiled uses for autotiling.
+#[derive(Debug, Clone)]
+pub struct TiledWangSet {
+    pub name: String,
+    pub set_type: WangSetType,
+    /// The local id of the tile representing the set, if it has one.
+    pub tile: Option<u32>,
+    /// The terrains of the set. Color `n` in a `WangId` is `colors[n - 1]`.
+    pub colors: Vec<TiledWangColor>,
+    /// The terrain colors of the tiles in the set, keyed by local tile id.
+    pub tiles: HashMap<u32, WangId>,
+    pub properties: TiledProperties,
+}
+
+/// A terrain of a wang set.
+#[derive(Debug, Clone)]
+pub struct TiledWangColor {
+    pub name: String,
+    /// The color Tiled shows the terrain with.
+    pub color: Color,
+    /// The local id of the tile representing the terrain, if it has one.
+    pub tile: Option<u32>,
+    /// How likely Tiled picks tiles with this terrain relative to the set's other terrains.
+    pub probability: f32,
+    pub properties: TiledProperties,
+}
+
+/// The terrain colors around a tile, starting at the top edge and going clockwise: top, top
+/// right, right, bottom right, bottom, bottom left, left and top left.
+///
+/// 0 means the edge or corner has no terrain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct WangId(pub [u8; 8]);
+
+impl WangId {
+    /// Returns the colors of the top, right, bottom and left edges.
+    pub fn edges(&self) -> [u8; 4] {
+        [self.0[0], self.0[2], self.0[4], self.0[6]]
+    }
+
+    /// Returns the colors of the top right, bottom right, bottom left and top left corners.
+    pub fn corners(&self) -> [u8; 4] {
+        [self.0[1], self.0[3], self.0[5], self.0[7]]
+    }
+}
@@ -0,0 +1,15 @@
+// Each test file only uses some of these.
+#![allow(dead_code)]
+
+use bevy_tiled::Map;
+
+/// The path of a file in `tests/maps`.
+pub fn map_path(name: &str) -> String {
+    format!("{}/tests/maps/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+/// Loads the map at `name` in `tests/maps`.
+pub fn load(name: &str) -> Map {
+    let path = map_path(name);
+    Map::from_bytes(&path, std::fs::read(&path).unwrap()).unwrap()
+}
@@ -0,0 +1,187 @@
+use bevy::prelude::*;
+use bevy_tiled::{Map, TileNeighborhood, TiledMapAnchor, TiledMapBuilder};
+
+/// Builds an orthogonal map of 4 by 3 tiles of 16 pixels.
+fn orthogonal_map() -> Map {
+    let mut builder = TiledMapBuilder::new(4, 3, 16, 16);
+    let first_gid = builder.add_tileset("outdoor", "ortho.png", 384, 192);
+    builder.add_layer("Ground", &[first_gid; 12]);
+    builder.build().unwrap()
+}
+
+/// Parses a 5 by 5 map with the given orientation and stagger settings. Hexagonal maps have a
+/// side length of 8 pixels.
+fn staggered_map(orientation: &str, axis: &str, index: &str) -> Map {
+    let (tile_width, tile_height) = match orientation {
+        "staggered" => (32, 16),
+        _ => (24, 24),
+    };
+    let document = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.4" orientation="{}" renderorder="right-down" width="5" height="5" tilewidth="{}" tileheight="{}" infinite="0" hexsidelength="8" staggeraxis="{}" staggerindex="{}">
+ <tileset firstgid="1" name="outdoor" tilewidth="16" tileheight="16" tilecount="288" columns="24">
+  <image source="ortho.png" width="384" height="192"/>
+ </tileset>
+ <layer id="1" name="Ground" width="5" height="5">
+  <data encoding="csv">{}</data>
+ </layer>
+</map>"#,
+        orientation,
+        tile_width,
+        tile_height,
+        axis,
+        index,
+        vec!["1"; 25].join(",")
+    );
+    Map::from_bytes("map.tmx", document.into_bytes()).unwrap()
+}
+
+fn staggered_maps() -> Vec<(String, Map)> {
+    let mut maps = Vec::new();
+    for orientation in ["hexagonal", "staggered"].iter() {
+        for axis in ["x", "y"].iter() {
+            for index in ["odd", "even"].iter() {
+                let name = format!("{} {} {}", orientation, axis, index);
+                maps.push((name, staggered_map(orientation, axis, index)));
+            }
+        }
+    }
+    maps
+}
+
+fn sorted_neighbors(map: &Map, x: u32, y: u32, neighborhood: TileNeighborhood) -> Vec<(u32, u32)> {
+    let mut neighbors = map
+        .neighbors(0, x, y, neighborhood)
+        .map(|(cell, _)| cell)
+        .collect::<Vec<_>>();
+    neighbors.sort();
+    neighbors
+}
+
+/// Returns where the center of a tile is drawn in bevy's world.
+fn world_position(map: &Map, anchor: TiledMapAnchor, x: u32, y: u32) -> Vec2 {
+    let transform = map.anchor_transform(Transform::identity(), anchor);
+    let position = transform
+        .value()
+        .transform_point3(map.tile_to_world(x, y).extend(0.0));
+    Vec2::new(position.x(), position.y())
+}
+
+#[test]
+fn first_tile_is_at_the_top_left() {
+    let map = orthogonal_map();
+    assert_eq!(
+        world_position(&map, TiledMapAnchor::TopLeft, 0, 0),
+        Vec2::new(8.0, -8.0)
+    );
+    // Rows go down and columns go right in bevy's y-up world, like in Tiled.
+    assert_eq!(
+        world_position(&map, TiledMapAnchor::TopLeft, 3, 2),
+        Vec2::new(56.0, -40.0)
+    );
+    assert_eq!(
+        world_position(&map, TiledMapAnchor::FirstTileCenter, 0, 0),
+        Vec2::zero()
+    );
+}
+
+#[test]
+fn cells_round_trip_through_the_world() {
+    let mut maps = staggered_maps();
+    for orientation in ["orthogonal", "isometric"].iter() {
+        maps.push((
+            orientation.to_string(),
+            staggered_map(orientation, "y", "odd"),
+        ));
+    }
+    for (name, map) in maps.iter() {
+        for y in 0..5 {
+            for x in 0..5 {
+                let world = map.tile_to_world(x, y);
+                assert_eq!(
+                    map.world_to_tile(world),
+                    Some((x, y)),
+                    "{} at {:?}",
+                    name,
+                    world
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn positions_outside_the_map_have_no_cell() {
+    for (name, map) in staggered_maps().iter() {
+        let far = map.tile_to_world(0, 0) - Vec2::new(100.0, -100.0);
+        assert_eq!(map.world_to_tile(far), None, "{}", name);
+    }
+}
+
+#[test]
+fn hexagons_have_six_neighbors() {
+    // Odd rows are shifted half a tile to the right, so their neighbors above and below are
+    // further right than those of even rows.
+    let map = staggered_map("hexagonal", "y", "odd");
+    assert_eq!(
+        sorted_neighbors(&map, 2, 1, TileNeighborhood::Four),
+        [(1, 1), (2, 0), (2, 2), (3, 0), (3, 1), (3, 2)]
+    );
+    assert_eq!(
+        sorted_neighbors(&map, 2, 2, TileNeighborhood::Four),
+        [(1, 1), (1, 2), (1, 3), (2, 1), (2, 3), (3, 2)]
+    );
+
+    let map = staggered_map("hexagonal", "y", "even");
+    assert_eq!(
+        sorted_neighbors(&map, 2, 1, TileNeighborhood::Four),
+        [(1, 0), (1, 1), (1, 2), (2, 0), (2, 2), (3, 1)]
+    );
+    assert_eq!(
+        sorted_neighbors(&map, 2, 2, TileNeighborhood::Four),
+        [(1, 2), (2, 1), (2, 3), (3, 1), (3, 2), (3, 3)]
+    );
+
+    // Even columns are shifted half a tile down.
+    let map = staggered_map("hexagonal", "x", "even");
+    assert_eq!(
+        sorted_neighbors(&map, 2, 2, TileNeighborhood::Four),
+        [(1, 2), (1, 3), (2, 1), (2, 3), (3, 2), (3, 3)]
+    );
+    assert_eq!(
+        sorted_neighbors(&map, 1, 2, TileNeighborhood::Four),
+        [(0, 1), (0, 2), (1, 1), (1, 3), (2, 1), (2, 2)]
+    );
+}
+
+#[test]
+fn staggered_diamonds_share_edges_with_the_rows_next_to_them() {
+    let map = staggered_map("staggered", "y", "odd");
+    assert_eq!(
+        sorted_neighbors(&map, 2, 1, TileNeighborhood::Four),
+        [(2, 0), (2, 2), (3, 0), (3, 2)]
+    );
+    assert_eq!(
+        sorted_neighbors(&map, 2, 2, TileNeighborhood::Four),
+        [(1, 1), (1, 3), (2, 1), (2, 3)]
+    );
+    // Corners touch the cells beside the tile and two rows away.
+    assert_eq!(
+        sorted_neighbors(&map, 2, 2, TileNeighborhood::Eight),
+        [
+            (1, 1),
+            (1, 2),
+            (1, 3),
+            (2, 0),
+            (2, 1),
+            (2, 3),
+            (2, 4),
+            (3, 2)
+        ]
+    );
+    // Cells outside of the map are skipped.
+    assert_eq!(
+        sorted_neighbors(&map, 0, 0, TileNeighborhood::Eight),
+        [(0, 1), (0, 2), (1, 0)]
+    );
+}
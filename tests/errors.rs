@@ -0,0 +1,117 @@
+use bevy_tiled::{Map, TiledError};
+
+const PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/maps/broken.tmx");
+
+/// Returns a 2x2 map with one tile layer holding `data`.
+fn map_with_layer_data(data: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.4" orientation="orthogonal" renderorder="right-down" width="2" height="2" tilewidth="16" tileheight="16" infinite="0">
+ <tileset firstgid="1" name="outdoor" tilewidth="16" tileheight="16" tilecount="288" columns="24">
+  <image source="../../assets/ortho.png" width="384" height="192"/>
+ </tileset>
+ <layer id="1" name="Ground" width="2" height="2">
+  {}
+ </layer>
+</map>"#,
+        data
+    )
+}
+
+fn load(document: &str) -> Result<Map, TiledError> {
+    Map::from_bytes(PATH, document.as_bytes().to_vec())
+}
+
+#[test]
+fn csv_layer_loads() {
+    let map = load(&map_with_layer_data(
+        r#"<data encoding="csv">1,2,
+3,4</data>"#,
+    ))
+    .unwrap();
+    assert_eq!(map.get_tile(0, 1, 1), Some(4));
+}
+
+#[test]
+fn invalid_xml_is_an_error() {
+    let error = load("<map><layer></map>").unwrap_err();
+    assert!(matches!(error, TiledError::Xml(_)), "{:?}", error);
+}
+
+#[test]
+fn invalid_utf8_is_an_error() {
+    let error = Map::from_bytes(PATH, vec![b'<', 0xff, b'>']).unwrap_err();
+    assert!(matches!(error, TiledError::Utf8(_)), "{:?}", error);
+}
+
+#[test]
+fn missing_attribute_is_an_error() {
+    let document = map_with_layer_data(r#"<data encoding="csv">1,2,3,4</data>"#)
+        .replace(r#" width="2" height="2" tilewidth"#, r#" tilewidth"#);
+    let error = load(&document).unwrap_err();
+    assert!(
+        matches!(error, TiledError::MissingAttribute(_)),
+        "{:?}",
+        error
+    );
+}
+
+#[test]
+fn unknown_encoding_is_an_error() {
+    let error = load(&map_with_layer_data(
+        r#"<data encoding="hex">01020304</data>"#,
+    ))
+    .unwrap_err();
+    assert!(matches!(error, TiledError::BadEncoding(_)), "{:?}", error);
+}
+
+#[test]
+fn invalid_base64_is_an_error() {
+    let error = load(&map_with_layer_data(
+        r#"<data encoding="base64">not base64!</data>"#,
+    ))
+    .unwrap_err();
+    assert!(matches!(error, TiledError::Base64(_)), "{:?}", error);
+}
+
+#[test]
+fn invalid_csv_gid_is_an_error() {
+    let error = load(&map_with_layer_data(
+        r#"<data encoding="csv">1,2,x,4</data>"#,
+    ))
+    .unwrap_err();
+    assert!(matches!(error, TiledError::IntParse(_)), "{:?}", error);
+}
+
+#[test]
+fn missing_tileset_file_is_an_error() {
+    let document = map_with_layer_data(r#"<data encoding="csv">1,2,3,4</data>"#).replace(
+        r#"<tileset firstgid="1" name="outdoor""#,
+        r#"<tileset firstgid="300" source="missing.tsx"/><tileset firstgid="1" name="outdoor""#,
+    );
+    match load(&document).unwrap_err() {
+        TiledError::Io { path, .. } => assert!(path.ends_with("tests/maps/missing.tsx")),
+        error => panic!("expected an io error, got {:?}", error),
+    }
+}
+
+#[test]
+fn truncated_layer_is_an_error() {
+    let error = load(&map_with_layer_data(
+        r#"<data encoding="csv">1,2,
+3</data>"#,
+    ))
+    .unwrap_err();
+    match error {
+        TiledError::LayerSizeMismatch {
+            layer,
+            expected,
+            found,
+        } => {
+            assert_eq!(layer, "Ground");
+            assert_eq!(expected, 4);
+            assert_eq!(found, 3);
+        }
+        error => panic!("expected a layer size mismatch, got {:?}", error),
+    }
+}
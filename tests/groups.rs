@@ -0,0 +1,33 @@
+use bevy::render::color::Color;
+use common::load;
+
+mod common;
+
+fn assert_color_eq(color: Option<Color>, expected: [f32; 4]) {
+    let color = color.expect("expected a tint color");
+    let actual = [color.r, color.g, color.b, color.a];
+    for (actual, expected) in actual.iter().zip(expected.iter()) {
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "expected {:?}, got {:?}",
+            expected,
+            actual
+        );
+    }
+}
+
+#[test]
+fn group_tints_multiply_with_layer_tints() {
+    let map = load("group-tint.tmx");
+    let half = 128.0 / 255.0;
+
+    assert_eq!(map.layers[0].name, "Untinted");
+    assert_color_eq(map.layers[0].tint_color, [half, half, 1.0, 1.0]);
+
+    // #8080ff from the outer group, #ffff00 from the inner one and #80ff8000 on the layer.
+    assert_eq!(map.layers[1].name, "Glow");
+    assert_color_eq(map.layers[1].tint_color, [half, half * half, 0.0, half]);
+
+    assert_eq!(map.layers[2].name, "Outside");
+    assert_eq!(map.layers[2].tint_color, None);
+}
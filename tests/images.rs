@@ -0,0 +1,41 @@
+use bevy_tiled::{Map, TiledError};
+
+const PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/maps/embedded-image.tmx");
+
+fn embedded_image_map() -> String {
+    std::fs::read_to_string(PATH).unwrap()
+}
+
+#[test]
+fn embedded_png_images_are_decoded() {
+    let map = Map::from_bytes(PATH, embedded_image_map().into_bytes()).unwrap();
+
+    let image = &map.tileset_embedded_images[&1];
+    assert_eq!(image.texture.size.x(), 32.0);
+    assert_eq!(image.texture.size.y(), 16.0);
+    // The first tile is red and the second blue.
+    assert_eq!(&image.texture.data[..4], &[255, 0, 0, 255]);
+    assert_eq!(&image.texture.data[16 * 4..16 * 4 + 4], &[0, 0, 255, 255]);
+    assert!(map.tileset_image_paths.is_empty());
+    assert!(map.tileset_atlases.contains_key(&1));
+}
+
+#[test]
+fn unsupported_embedded_formats_are_named() {
+    let document = embedded_image_map().replace("format=\"png\"", "format=\"jpg\"");
+    match Map::from_bytes(PATH, document.into_bytes()) {
+        Err(TiledError::Unsupported(message)) => {
+            assert!(message.contains("jpg"), "unexpected message {:?}", message)
+        }
+        result => panic!("expected an unsupported format, got {:?}", result),
+    }
+}
+
+#[test]
+fn invalid_embedded_images_fail_to_load() {
+    let document = embedded_image_map().replace("iVBORw0KGgo", "AAAAAAAAAAA");
+    assert!(matches!(
+        Map::from_bytes(PATH, document.into_bytes()),
+        Err(TiledError::InvalidImage { tileset, .. }) if tileset == "colors"
+    ));
+}
@@ -0,0 +1,47 @@
+use bevy_tiled::Map;
+use common::{load, map_path};
+
+mod common;
+
+#[test]
+fn tile_elements_load_like_csv() {
+    let map = load("tile-elements.tmx");
+    let grid = map.tile_grid(0).unwrap();
+    let rows = grid
+        .rows()
+        .map(|row| row.collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    assert_eq!(rows, [[151, 0, 101], [2, 102, 0]]);
+    match &map.map.layers[0].tiles {
+        tiled::LayerData::Finite(rows) => assert!(rows[1][0].flip_h),
+        tiled::LayerData::Infinite(_) => panic!("expected a finite layer"),
+    }
+}
+
+#[test]
+fn zlib_layers_round_trip() {
+    let map = load("zlib.tmx");
+    let rows = map
+        .tile_grid(0)
+        .unwrap()
+        .rows()
+        .map(|row| row.collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rows,
+        [
+            [1, 2, 3, 4],
+            [25, 26, 27, 28],
+            [0, 0, 151, 152],
+            [288, 0, 101, 102]
+        ]
+    );
+    match &map.map.layers[0].tiles {
+        tiled::LayerData::Finite(rows) => assert!(rows[2][3].flip_h),
+        tiled::LayerData::Infinite(_) => panic!("expected a finite layer"),
+    }
+
+    let path = map_path("zlib.tmx");
+    let saved = Map::from_bytes(&path, map.to_tmx_string().unwrap().into_bytes()).unwrap();
+    assert_eq!(saved.map.layers[0].tiles, map.map.layers[0].tiles);
+}
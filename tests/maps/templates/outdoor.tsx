@@ -0,0 +1,4 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.4" tiledversion="1.4.2" name="outdoor" tilewidth="16" tileheight="16" tilecount="288" columns="24">
+ <image source="../../../assets/ortho.png" width="384" height="192"/>
+</tileset>
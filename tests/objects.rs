@@ -0,0 +1,35 @@
+use bevy_tiled::TiledObjectShape;
+use common::load;
+use glam::Vec2;
+
+mod common;
+
+#[test]
+fn concave_polygons_keep_their_points_in_order() {
+    let map = load("shapes.tmx");
+
+    let arrow = map.object_by_name("Arrow").unwrap();
+    assert_eq!(
+        arrow.shape,
+        TiledObjectShape::Polygon(vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(24.0, -16.0),
+            // The notch that makes the arrow concave.
+            Vec2::new(16.0, 0.0),
+            Vec2::new(24.0, 16.0),
+            Vec2::new(-8.5, 8.0),
+        ])
+    );
+    assert_eq!(arrow.position, Vec2::new(32.0, 32.0));
+
+    let ledge = map.object_by_name("Ledge").unwrap();
+    assert_eq!(
+        ledge.shape.points(),
+        &[
+            Vec2::new(0.0, 0.0),
+            Vec2::new(-4.25, -12.0),
+            Vec2::new(20.0, -12.5),
+        ][..]
+    );
+    assert!(matches!(ledge.shape, TiledObjectShape::Polyline(_)));
+}
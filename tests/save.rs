@@ -0,0 +1,39 @@
+use bevy_tiled::Map;
+
+const PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/maps/save.tmx");
+
+#[test]
+fn saved_map_loads_the_same() {
+    let map = Map::from_bytes(PATH, std::fs::read(PATH).unwrap()).unwrap();
+    let saved = map.to_tmx_string().unwrap();
+    let loaded = Map::from_bytes(PATH, saved.into_bytes()).unwrap();
+
+    let layer_names = |map: &Map| {
+        map.map
+            .layers
+            .iter()
+            .map(|layer| layer.name.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(layer_names(&loaded), ["Ground", "Flipped"]);
+    assert_eq!(layer_names(&loaded), layer_names(&map));
+    for (loaded_layer, layer) in loaded.map.layers.iter().zip(map.map.layers.iter()) {
+        assert_eq!(loaded_layer.tiles, layer.tiles);
+    }
+    match &loaded.map.layers[1].tiles {
+        tiled::LayerData::Finite(rows) => {
+            assert_eq!(rows[0][2], tiled::LayerTile::new(2147483650));
+            assert_eq!(rows[2][3], tiled::LayerTile::new(536870916));
+        }
+        tiled::LayerData::Infinite(_) => panic!("expected a finite layer"),
+    }
+
+    let object_ids = |map: &Map| {
+        map.object_layers
+            .iter()
+            .flat_map(|layer| layer.objects.iter().map(|object| object.id))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(object_ids(&loaded), [5, 7]);
+    assert_eq!(object_ids(&loaded), object_ids(&map));
+}
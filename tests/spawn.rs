@@ -0,0 +1,352 @@
+use bevy::{ecs::Schedule, prelude::*};
+use bevy_tiled::*;
+
+mod common;
+
+/// Runs the map systems without the render plugins, which need a window.
+struct Harness {
+    world: World,
+    resources: Resources,
+    schedule: Schedule,
+}
+
+impl Harness {
+    fn new() -> Self {
+        let mut resources = Resources::default();
+        resources.insert(AssetServer::default());
+        resources.insert(TiledTextures::default());
+        resources.insert(Assets::<Map>::default());
+        resources.insert(Assets::<Mesh>::default());
+        resources.insert(Assets::<ColorMaterial>::default());
+        resources.insert(Assets::<Texture>::default());
+        resources.insert(Assets::<TextureAtlas>::default());
+        resources.insert(Events::<AssetEvent<Map>>::default());
+        resources.insert(Events::<MapSpawned>::default());
+        resources.insert(Events::<DespawnTiledMap>::default());
+        resources.insert(ClearColor::default());
+        resources.insert(TiledLayerRefreshes::default());
+        resources.insert(Time::default());
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update");
+        schedule.add_system_to_stage("update", Assets::<Map>::asset_event_system.system());
+        schedule.add_system_to_stage("update", process_loaded_tile_maps.system());
+        schedule.add_system_to_stage("update", add_embedded_images.system());
+        schedule.add_system_to_stage("update", respawn_refreshed_layers.system());
+        schedule.add_system_to_stage("update", despawn_tiled_maps.system());
+        schedule.add_system_to_stage("update", animate_tile_sprites.system());
+        schedule.add_system_to_stage("update", apply_layer_visibility.system());
+        Harness {
+            world: World::default(),
+            resources,
+            schedule,
+        }
+    }
+
+    fn add_map(&mut self, name: &str) -> Handle<Map> {
+        self.resources
+            .get_mut::<Assets<Map>>()
+            .unwrap()
+            .add(common::load(name))
+    }
+
+    fn spawn_map(&mut self, map: Handle<Map>, render_mode: TileRenderMode) -> Entity {
+        self.world.spawn(TiledMapComponents {
+            map_asset: map,
+            render_mode,
+            ..Default::default()
+        })
+    }
+
+    fn update(&mut self) {
+        self.schedule
+            .initialize(&mut self.world, &mut self.resources);
+        self.schedule.run(&mut self.world, &mut self.resources);
+    }
+
+    fn commands(&mut self, write: impl FnOnce(&mut Commands)) {
+        let mut commands = Commands::default();
+        commands.set_entity_reserver(self.world.get_entity_reserver());
+        write(&mut commands);
+        commands.apply(&mut self.world, &mut self.resources);
+    }
+
+    /// The tile sprites spawned for a map entity, sorted by layer and cell.
+    fn tile_sprites(&self, map_entity: Entity) -> Vec<(Entity, usize, u32, u32)> {
+        let mut sprites = self
+            .world
+            .query::<(Entity, &TiledTileSprite, &TiledMapEntity)>()
+            .iter()
+            .filter(|(_, _, spawned_for)| spawned_for.entity == map_entity)
+            .map(|(entity, sprite, _)| (entity, sprite.layer_index, sprite.x, sprite.y))
+            .collect::<Vec<_>>();
+        sprites.sort_by_key(|(_, layer_index, x, y)| (*layer_index, *y, *x));
+        sprites
+    }
+}
+
+#[test]
+fn refresh_layer_only_respawns_that_layer() {
+    let mut harness = Harness::new();
+    let map = harness.add_map("save.tmx");
+    let map_entity = harness.spawn_map(map, TileRenderMode::Sprites);
+    harness.update();
+
+    let before = harness.tile_sprites(map_entity);
+    // 11 ground tiles and 3 flipped ones.
+    assert_eq!(before.len(), 14);
+
+    {
+        let mut maps = harness.resources.get_mut::<Assets<Map>>().unwrap();
+        let edited = map_mut_without_event(&mut maps, map).unwrap();
+        if let tiled::LayerData::Finite(rows) = &mut edited.map.layers[1].tiles {
+            rows[0][0] = tiled::LayerTile::new(1);
+        }
+        edited.rebuild_layer(1).unwrap();
+    }
+    harness.commands(|commands| refresh_layer(commands, map_entity, 1));
+    harness.update();
+
+    let after = harness.tile_sprites(map_entity);
+    let layer = |sprites: &[(Entity, usize, u32, u32)], layer_index| {
+        sprites
+            .iter()
+            .filter(|sprite| sprite.1 == layer_index)
+            .copied()
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(layer(&after, 0), layer(&before, 0));
+    let refreshed = layer(&after, 1);
+    assert_eq!(
+        refreshed
+            .iter()
+            .map(|(_, _, x, y)| (*x, *y))
+            .collect::<Vec<_>>(),
+        vec![(0, 0), (2, 0), (0, 1), (3, 2)]
+    );
+    for sprite in refreshed {
+        assert!(!before.iter().any(|(entity, ..)| *entity == sprite.0));
+    }
+}
+
+#[test]
+fn each_object_spawns_an_entity() {
+    let mut harness = Harness::new();
+    let map = harness.add_map("save.tmx");
+    harness.spawn_map(map, TileRenderMode::Mesh);
+    harness.update();
+
+    let mut names = harness
+        .world
+        .query::<&TiledObject>()
+        .iter()
+        .map(|object| object.name.clone())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, vec!["Chest", "Spawn"]);
+}
+
+#[test]
+fn despawning_a_map_removes_everything_spawned_for_it() {
+    for render_mode in [TileRenderMode::Mesh, TileRenderMode::Sprites].iter() {
+        let mut harness = Harness::new();
+        let map = harness.add_map("save.tmx");
+        harness.spawn_map(map, *render_mode);
+        harness.update();
+        assert!(harness.world.query::<&TiledMapEntity>().iter().count() > 0);
+
+        harness
+            .resources
+            .get_mut::<Events<DespawnTiledMap>>()
+            .unwrap()
+            .send(DespawnTiledMap { map });
+        harness.update();
+
+        assert_eq!(harness.world.query::<Entity>().iter().count(), 0);
+    }
+}
+
+#[test]
+fn maps_spawn_independent_entities() {
+    let mut harness = Harness::new();
+    let first_map = harness.add_map("save.tmx");
+    let second_map = harness.add_map("group-tint.tmx");
+    let first = harness.spawn_map(first_map, TileRenderMode::Sprites);
+    let second = harness.spawn_map(second_map, TileRenderMode::Sprites);
+    harness.update();
+
+    let spawned_for = |harness: &Harness, map_entity: Entity| {
+        let mut entities = harness
+            .world
+            .get::<TiledMapEntities>(map_entity)
+            .unwrap()
+            .0
+            .clone();
+        entities.sort();
+        entities
+    };
+    let first_entities = spawned_for(&harness, first);
+    let second_entities = spawned_for(&harness, second);
+    assert!(!first_entities.is_empty() && !second_entities.is_empty());
+    assert!(first_entities
+        .iter()
+        .all(|entity| !second_entities.contains(entity)));
+    for (map, map_entity, entities) in [
+        (first_map, first, &first_entities),
+        (second_map, second, &second_entities),
+    ]
+    .iter()
+    {
+        for entity in entities.iter() {
+            assert_eq!(
+                *harness.world.get::<TiledMapEntity>(*entity).unwrap(),
+                TiledMapEntity {
+                    map: *map,
+                    entity: *map_entity,
+                }
+            );
+        }
+    }
+
+    harness
+        .resources
+        .get_mut::<Events<DespawnTiledMap>>()
+        .unwrap()
+        .send(DespawnTiledMap { map: first_map });
+    harness.update();
+    assert!(harness.world.get::<TiledMapEntities>(first).is_err());
+    assert_eq!(spawned_for(&harness, second), second_entities);
+    for entity in second_entities {
+        assert!(harness.world.get::<TiledMapEntity>(entity).is_ok());
+    }
+}
+
+#[test]
+fn flipped_tile_objects_animate() {
+    let mut harness = Harness::new();
+    let map = harness.add_map("animated-object.tmx");
+    harness.spawn_map(map, TileRenderMode::Mesh);
+    harness.update();
+
+    let torch = harness
+        .world
+        .query::<(Entity, &TiledObject)>()
+        .iter()
+        .find(|(_, object)| object.name == "Torch")
+        .map(|(entity, _)| entity)
+        .unwrap();
+    let transform = *harness.world.get::<Transform>(torch).unwrap();
+    assert!(
+        transform.value().x_axis().x() < 0.0,
+        "the torch should be flipped"
+    );
+
+    // Frames of tiles 4, 5 and 6, lasting 100, 100 and 200 milliseconds.
+    for (seconds, index) in [(0.0, 4), (0.15, 5), (0.25, 6), (0.45, 4)].iter() {
+        harness
+            .resources
+            .get_mut::<Time>()
+            .unwrap()
+            .seconds_since_startup = *seconds;
+        harness.update();
+        assert_eq!(
+            harness
+                .world
+                .get::<TextureAtlasSprite>(torch)
+                .unwrap()
+                .index,
+            *index,
+            "at {} seconds",
+            seconds
+        );
+        assert_eq!(*harness.world.get::<Transform>(torch).unwrap(), transform);
+    }
+}
+
+#[test]
+fn embedded_images_are_added_as_textures() {
+    let mut harness = Harness::new();
+    let map = harness.add_map("embedded-image.tmx");
+    let map_entity = harness.spawn_map(map, TileRenderMode::Sprites);
+    harness.update();
+
+    let handle = harness
+        .resources
+        .get::<Assets<Map>>()
+        .unwrap()
+        .get(&map)
+        .unwrap()
+        .tileset_embedded_images[&1]
+        .handle;
+    let textures = harness.resources.get::<Assets<Texture>>().unwrap();
+    assert_eq!(textures.get(&handle).unwrap().size, Vec2::new(32.0, 16.0));
+    assert_eq!(harness.tile_sprites(map_entity).len(), 2);
+}
+
+#[test]
+fn tiles_past_every_tileset_are_skipped() {
+    for render_mode in [TileRenderMode::Mesh, TileRenderMode::Sprites].iter() {
+        let mut harness = Harness::new();
+        let map = harness.add_map("orphan-tiles.tmx");
+        let map_entity = harness.spawn_map(map, *render_mode);
+        harness.update();
+
+        let cells = harness
+            .tile_sprites(map_entity)
+            .into_iter()
+            .map(|(_, _, x, y)| (x, y))
+            .collect::<Vec<_>>();
+        match render_mode {
+            TileRenderMode::Sprites => assert_eq!(cells, vec![(0, 0), (2, 0)]),
+            TileRenderMode::Mesh => {
+                assert!(cells.is_empty());
+                let meshes = harness.resources.get::<Assets<Mesh>>().unwrap();
+                let mut chunks = harness.world.query::<(&TileMapChunk, &Handle<Mesh>)>();
+                let indices = chunks
+                    .iter()
+                    .map(|(_, mesh)| meshes.get(mesh).unwrap().indices.as_ref().unwrap().len())
+                    .sum::<usize>();
+                // Two tiles of two triangles each.
+                assert_eq!(indices, 12);
+            }
+        }
+    }
+}
+
+#[test]
+fn hiding_a_merged_layer_hides_its_chunks() {
+    let mut harness = Harness::new();
+    let map = harness.add_map("save.tmx");
+    harness.world.spawn(TiledMapComponents {
+        map_asset: map,
+        merge_tile_layers: TiledMapMergeLayers {
+            enabled: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    harness.update();
+
+    let chunks_visible = |harness: &Harness| {
+        harness
+            .world
+            .query::<(&TileMapChunk, &Draw)>()
+            .iter()
+            .map(|(chunk, draw)| (chunk.layer_id, draw.is_visible))
+            .collect::<Vec<_>>()
+    };
+    // Both tile layers are drawn with the chunk of the first one.
+    assert_eq!(chunks_visible(&harness), vec![(0.0, true)]);
+
+    for mut layer in harness
+        .world
+        .query_mut::<&mut TiledLayerVisibility>()
+        .iter()
+    {
+        if layer.layer_index == 1 {
+            layer.visible = false;
+        }
+    }
+    harness.update();
+    assert_eq!(chunks_visible(&harness), vec![(0.0, false)]);
+}
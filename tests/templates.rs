@@ -0,0 +1,43 @@
+use bevy_tiled::{Map, PropertyValue, TiledObject};
+use common::load;
+
+mod common;
+
+fn object(map: &Map, id: u32) -> &TiledObject {
+    map.object_layers[0]
+        .objects
+        .iter()
+        .find(|object| object.id == id)
+        .unwrap()
+}
+
+#[test]
+fn objects_override_their_template() {
+    let map = load("templates/templates.tmx");
+
+    let goblin = object(&map, 1);
+    assert_eq!(goblin.name, "Goblin");
+    assert_eq!(goblin.class.as_deref(), Some("enemy"));
+    assert_eq!(goblin.properties["health"], PropertyValue::Int(25));
+    assert_eq!(
+        goblin.properties["loot"],
+        PropertyValue::String("gold".to_string())
+    );
+
+    let boss = object(&map, 2);
+    assert_eq!(boss.name, "Boss");
+    assert_eq!(boss.properties["health"], PropertyValue::Int(10));
+    assert_eq!(boss.size.x(), 16.0);
+}
+
+#[test]
+fn template_gids_are_moved_to_the_map_tileset() {
+    let map = load("templates/templates.tmx");
+
+    // The template's tile 3 of props.tsx, which the map has from gid 289.
+    for id in [1, 2].iter() {
+        let object = object(&map, *id);
+        assert_eq!(object.gid, 291);
+        assert!(object.flip_h && !object.flip_v && !object.flip_d);
+    }
+}
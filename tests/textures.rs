@@ -0,0 +1,85 @@
+use bevy::{
+    asset::{update_asset_storage_system, AssetPlugin},
+    ecs::Schedule,
+    prelude::*,
+    render::texture::{ImageTextureLoader, TextureFormat},
+    type_registry::TypeRegistryPlugin,
+};
+use bevy_tiled::{draw_missing_images, TiledTextures};
+use std::{path::Path, thread, time::Duration};
+
+fn textures_with_placeholder(textures: &mut Assets<Texture>) -> TiledTextures {
+    TiledTextures {
+        placeholder: Some(textures.add(Texture::new(
+            Vec2::new(1.0, 1.0),
+            vec![255, 0, 255, 255],
+            TextureFormat::Rgba8UnormSrgb,
+        ))),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn missing_images_are_drawn_with_the_placeholder() {
+    let mut app = App::build();
+    app.add_plugin(TypeRegistryPlugin)
+        .add_plugin(AssetPlugin)
+        .add_asset::<Texture>()
+        .add_asset_loader::<Texture, ImageTextureLoader>();
+    let App {
+        mut world,
+        mut resources,
+        ..
+    } = app.app;
+    let path = Path::new("assets/missing.png");
+    let (handle, tiled_textures) = {
+        let mut tiled_textures =
+            textures_with_placeholder(&mut resources.get_mut::<Assets<Texture>>().unwrap());
+        let asset_server = resources.get::<AssetServer>().unwrap();
+        let handle = tiled_textures.load(&asset_server, path);
+        assert_ne!(Some(handle), tiled_textures.placeholder);
+        assert_eq!(tiled_textures.load(&asset_server, path), handle);
+        (handle, tiled_textures)
+    };
+    resources.insert(tiled_textures);
+
+    let mut schedule = Schedule::default();
+    schedule.add_stage("update");
+    schedule.add_system_to_stage("update", update_asset_storage_system::<Texture>.system());
+    schedule.add_system_to_stage("update", draw_missing_images.system());
+    // The image is read on the asset server's loader thread.
+    for _ in 0..500 {
+        schedule.run(&mut world, &mut resources);
+        if !resources.get::<TiledTextures>().unwrap().missing.is_empty() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    let mut tiled_textures = resources.get_mut::<TiledTextures>().unwrap();
+    assert!(tiled_textures.missing.contains(path));
+    let textures = resources.get::<Assets<Texture>>().unwrap();
+    assert_eq!(textures.get(&handle).unwrap().size, Vec2::new(1.0, 1.0));
+
+    tiled_textures.forget_missing();
+    assert!(tiled_textures.handles.is_empty());
+}
+
+#[test]
+fn images_without_a_loader_are_cached_with_the_placeholder() {
+    // No loader is registered for png, so the asset server can't start loading this image
+    // even though it exists.
+    let asset_server = AssetServer::default();
+    let path = Path::new("assets/ortho.png");
+
+    let mut tiled_textures = textures_with_placeholder(&mut Assets::default());
+    let placeholder = tiled_textures.placeholder.unwrap();
+    assert_eq!(tiled_textures.load(&asset_server, path), placeholder);
+    assert_eq!(tiled_textures.handles[path], placeholder);
+    assert!(tiled_textures.missing.contains(path));
+    tiled_textures.forget_missing();
+    assert!(tiled_textures.handles.is_empty());
+
+    let mut tiled_textures = TiledTextures::default();
+    assert_eq!(tiled_textures.load(&asset_server, path), Handle::default());
+}
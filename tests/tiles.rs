@@ -0,0 +1,70 @@
+use bevy::{ecs::Schedule, prelude::*};
+use bevy_tiled::{map_mut_without_event, respawn_changed_tiles, set_map_tile, Map, TileChanged};
+use common::load;
+
+mod common;
+
+#[test]
+fn set_map_tile_changes_the_tile_and_sends_tile_changed() {
+    let mut maps = Assets::<Map>::default();
+    let handle = maps.add(load("../../assets/ortho-map.tmx"));
+    let previous_gid = maps.get(&handle).unwrap().get_tile(0, 3, 2).unwrap();
+    let gid = if previous_gid == 5 { 6 } else { 5 };
+
+    set_map_tile(&mut maps, &handle, 0, 3, 2, gid).unwrap();
+    assert_eq!(maps.get(&handle).unwrap().get_tile(0, 3, 2), Some(gid));
+
+    let mut world = World::default();
+    let mut resources = Resources::default();
+    resources.insert(maps);
+    resources.insert(Assets::<Mesh>::default());
+    resources.insert(Events::<TileChanged>::default());
+    let mut schedule = Schedule::default();
+    schedule.add_stage("update");
+    schedule.add_system_to_stage("update", respawn_changed_tiles.system());
+    schedule.run(&mut world, &mut resources);
+
+    let events = resources.get::<Events<TileChanged>>().unwrap();
+    let changed = events
+        .get_reader()
+        .iter(&events)
+        .map(|event| {
+            (
+                event.map,
+                event.layer_index,
+                event.x,
+                event.y,
+                event.gid,
+                event.previous_gid,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(changed, vec![(handle, 0, 3, 2, gid, previous_gid)]);
+}
+
+#[test]
+fn set_map_tile_fails_for_maps_that_are_not_loaded() {
+    let mut maps = Assets::<Map>::default();
+    assert!(set_map_tile(&mut maps, &Handle::new(), 0, 0, 0, 1).is_err());
+}
+
+#[test]
+fn editing_a_map_without_event_sends_no_asset_event() {
+    let mut maps = Assets::<Map>::default();
+    let handle = maps.add(load("../../assets/ortho-map.tmx"));
+    assert!(map_mut_without_event(&mut maps, handle).is_some());
+
+    let mut world = World::default();
+    let mut resources = Resources::default();
+    resources.insert(maps);
+    resources.insert(Events::<AssetEvent<Map>>::default());
+    let mut schedule = Schedule::default();
+    schedule.add_stage("update");
+    schedule.add_system_to_stage("update", Assets::<Map>::asset_event_system.system());
+    schedule.run(&mut world, &mut resources);
+
+    let events = resources.get::<Events<AssetEvent<Map>>>().unwrap();
+    let events = events.get_reader().iter(&events).collect::<Vec<_>>();
+    assert_eq!(events.len(), 1);
+    assert!(matches!(events[0], AssetEvent::Created { handle: created } if *created == handle));
+}
@@ -0,0 +1,32 @@
+use common::load;
+
+mod common;
+
+#[test]
+fn gids_resolve_with_tilesets_out_of_order() {
+    let map = load("unordered-tilesets.tmx");
+
+    let tileset_name = |gid| map.tileset_by_gid(gid).map(|tileset| tileset.name.as_str());
+    assert_eq!(tileset_name(1), Some("outdoor"));
+    assert_eq!(tileset_name(288), Some("outdoor"));
+    assert_eq!(tileset_name(289), Some("props"));
+    assert_eq!(tileset_name(290), Some("props"));
+
+    let local_ids = map
+        .tile_grid(0)
+        .unwrap()
+        .rows()
+        .flatten()
+        .map(|gid| map.tileset_and_local_id(gid).map(|(_, local_id)| local_id))
+        .collect::<Vec<_>>();
+    assert_eq!(local_ids, vec![Some(0), Some(287), Some(1)]);
+}
+
+#[test]
+fn gids_past_every_tileset_have_no_tileset() {
+    let map = load("orphan-tiles.tmx");
+
+    assert_eq!(map.get_tile(0, 1, 0), Some(500));
+    assert!(map.tileset_by_gid(500).is_none());
+    assert!(map.tileset_and_local_id(500).is_none());
+}